@@ -0,0 +1,165 @@
+use std::error::Error;
+
+use lum_libs::{
+    serde::{ser::Error as _, Deserialize, Serialize},
+    serde_json::{self, ser::PrettyFormatter, Serializer},
+};
+
+/// A trait that defines how configuration documents are converted from and to their textual representation.
+///
+/// Implement this trait to use a file format that is not bundled with this crate.
+/// The `Error` type is used to indicate the error that may occur during (de)serialization.
+/// It is boxed into the `Codec` variants of [crate::FileConfigParseError] and [crate::ConfigSaveError].
+///
+/// # Examples
+///
+/// ```
+/// use lum_config::Codec;
+/// use lum_libs::{serde::{Deserialize, Serialize}, serde_json};
+///
+/// struct CompactJsonCodec;
+///
+/// impl Codec for CompactJsonCodec {
+///     type Error = serde_json::Error;
+///
+///     fn to_string<T: Serialize>(&self, value: &T) -> Result<String, Self::Error> {
+///         serde_json::to_string(value)
+///     }
+///
+///     fn from_str<T: for<'de> Deserialize<'de>>(&self, input: &str) -> Result<T, Self::Error> {
+///         serde_json::from_str(input)
+///     }
+/// }
+///
+/// let output = CompactJsonCodec.to_string(&vec![1, 2]).unwrap();
+/// assert_eq!(output, "[1,2]");
+/// ```
+pub trait Codec {
+    type Error: Error + Send + Sync + 'static;
+
+    /// Serializes a value into its textual representation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the serialized `String`.
+    /// * Failure is indicated by an `Err` value, containing an instance of the error type.
+    fn to_string<T: Serialize>(&self, value: &T) -> Result<String, Self::Error>;
+
+    /// Deserializes a value from its textual representation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the deserialized value.
+    /// * Failure is indicated by an `Err` value, containing an instance of the error type.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_str<T: for<'de> Deserialize<'de>>(&self, input: &str) -> Result<T, Self::Error>;
+}
+
+/// The indentation used by [JsonStyle] for pretty-printed JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonIndent {
+    /// Indents each level with the given number of spaces.
+    Spaces(usize),
+    /// Indents each level with a single tab.
+    Tabs,
+}
+
+/// The formatting used by [JsonCodec] when writing JSON.
+///
+/// Defaults to pretty-printed JSON, indented with two spaces.
+///
+/// # Fields
+///
+/// * `indent` - The indentation of pretty-printed JSON. Ignored if `compact` is set.
+/// * `compact` - Whether to write the whole document on a single line, without any whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonStyle {
+    pub indent: JsonIndent,
+    pub compact: bool,
+}
+
+impl JsonStyle {
+    /// Creates a pretty-printed `JsonStyle`, indented with the given number of spaces.
+    pub fn spaces(count: usize) -> Self {
+        JsonStyle {
+            indent: JsonIndent::Spaces(count),
+            compact: false,
+        }
+    }
+
+    /// Creates a pretty-printed `JsonStyle`, indented with tabs.
+    pub fn tabs() -> Self {
+        JsonStyle {
+            indent: JsonIndent::Tabs,
+            compact: false,
+        }
+    }
+
+    /// Creates a compact `JsonStyle`, writing the whole document on a single line.
+    pub fn compact() -> Self {
+        JsonStyle {
+            compact: true,
+            ..JsonStyle::default()
+        }
+    }
+}
+
+impl Default for JsonStyle {
+    fn default() -> Self {
+        JsonStyle::spaces(2)
+    }
+}
+
+/// The default [Codec], reading and writing JSON using `serde_json`.
+///
+/// The written JSON is formatted according to its [JsonStyle], which defaults to pretty-printed JSON indented with two spaces.
+///
+/// # Examples
+///
+/// ```
+/// use lum_config::{Codec, JsonCodec, JsonStyle};
+///
+/// let codec = JsonCodec::new(JsonStyle::tabs());
+/// let output = codec.to_string(&vec![1, 2]).unwrap();
+///
+/// assert_eq!(output, "[\n\t1,\n\t2\n]");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct JsonCodec {
+    pub style: JsonStyle,
+}
+
+impl JsonCodec {
+    /// Creates a new `JsonCodec` writing JSON in the given [JsonStyle].
+    pub fn new(style: JsonStyle) -> Self {
+        JsonCodec { style }
+    }
+}
+
+impl Codec for JsonCodec {
+    type Error = serde_json::Error;
+
+    fn to_string<T: Serialize>(&self, value: &T) -> Result<String, Self::Error> {
+        if self.style.compact {
+            return serde_json::to_string(value);
+        }
+
+        let indent = match self.style.indent {
+            JsonIndent::Spaces(count) => " ".repeat(count),
+            JsonIndent::Tabs => "\t".to_string(),
+        };
+
+        let mut writer = Vec::new();
+        let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = Serializer::with_formatter(&mut writer, formatter);
+        value.serialize(&mut serializer)?;
+
+        String::from_utf8(writer).map_err(serde_json::Error::custom)
+    }
+
+    fn from_str<T: for<'de> Deserialize<'de>>(&self, input: &str) -> Result<T, Self::Error> {
+        serde_json::from_str(input)
+    }
+}
@@ -0,0 +1,216 @@
+/// (De)serializes a `Duration` as a human-readable string like `"30s"` or `"1h 30m"`.
+///
+/// Parsing and formatting are done by `humantime`, so all of its units (`ns`, `us`, `ms`, `s`, `m`, `h`, `d`, ...)
+/// and combinations of them are supported. A string without a unit, e.g. `"30"`, is interpreted as seconds.
+///
+/// The value is always deserialized from a string, so it also works for configuration loaded from environment variables.
+/// Plain numbers have to be quoted as well, e.g. `"30"` instead of `30`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use lum_libs::{
+///     serde::{Deserialize, Serialize},
+///     serde_json,
+/// };
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "lum_config::config_serde::duration")]
+///     timeout: Duration,
+/// }
+///
+/// let config: Config = serde_json::from_str(r#"{ "timeout": "1m 30s" }"#).unwrap();
+/// assert_eq!(config.timeout, Duration::from_secs(90));
+///
+/// let json = serde_json::to_string(&config).unwrap();
+/// assert_eq!(json, r#"{"timeout":"1m 30s"}"#);
+/// ```
+pub mod duration {
+    use std::{fmt, time::Duration};
+
+    use lum_libs::{
+        humantime,
+        serde::{de, Deserializer, Serializer},
+    };
+
+    /// Serializes `duration` as a human-readable string, e.g. `"1h 30m"`.
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&humantime::format_duration(*duration))
+    }
+
+    /// Deserializes a `Duration` from a human-readable string, e.g. `"30s"`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        deserializer.deserialize_str(DurationVisitor)
+    }
+
+    struct DurationVisitor;
+
+    impl de::Visitor<'_> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a duration like \"30s\" or \"1h 30m\"")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+            let value = value.trim();
+            if let Ok(seconds) = value.parse::<u64>() {
+                return Ok(Duration::from_secs(seconds));
+            }
+
+            humantime::parse_duration(value)
+                .map_err(|error| E::custom(format!("invalid duration \"{}\": {}", value, error)))
+        }
+    }
+}
+
+/// (De)serializes a byte count (`u64`) as a human-readable string like `"10MB"` or `"1.5 GiB"`.
+///
+/// Decimal units (`KB`, `MB`, `GB`, `TB`, `PB`) are powers of 1000, binary units (`KiB`, `MiB`, `GiB`, `TiB`, `PiB`) are powers of 1024.
+/// Units are case-insensitive, the trailing `B` is optional, and a string without a unit, e.g. `"512"`, is interpreted as bytes.
+/// Fractions are supported as long as they amount to a whole number of bytes.
+///
+/// When serializing, the largest unit that represents the value exactly is used, so a round-trip never loses precision.
+///
+/// The value is always deserialized from a string, so it also works for configuration loaded from environment variables.
+/// Plain numbers have to be quoted as well, e.g. `"30"` instead of `30`.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::{
+///     serde::{Deserialize, Serialize},
+///     serde_json,
+/// };
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     #[serde(with = "lum_config::config_serde::byte_size")]
+///     max_size: u64,
+/// }
+///
+/// let config: Config = serde_json::from_str(r#"{ "max_size": "10MB" }"#).unwrap();
+/// assert_eq!(config.max_size, 10_000_000);
+///
+/// let config: Config = serde_json::from_str(r#"{ "max_size": "1.5 KiB" }"#).unwrap();
+/// assert_eq!(config.max_size, 1536);
+///
+/// let json = serde_json::to_string(&Config { max_size: 4 * 1024 * 1024 }).unwrap();
+/// assert_eq!(json, r#"{"max_size":"4MiB"}"#);
+/// ```
+pub mod byte_size {
+    use std::fmt;
+
+    use lum_libs::serde::{de, Deserializer, Serializer};
+
+    /// The units, ordered from the largest to the smallest multiplier.
+    const UNITS: [(&str, u64); 10] = [
+        ("PiB", 1 << 50),
+        ("PB", 1_000_000_000_000_000),
+        ("TiB", 1 << 40),
+        ("TB", 1_000_000_000_000),
+        ("GiB", 1 << 30),
+        ("GB", 1_000_000_000),
+        ("MiB", 1 << 20),
+        ("MB", 1_000_000),
+        ("KiB", 1 << 10),
+        ("KB", 1_000),
+    ];
+
+    /// Serializes `bytes` as a human-readable string, e.g. `"10MB"`.
+    pub fn serialize<S: Serializer>(bytes: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        let unit = UNITS
+            .iter()
+            .find(|(_, multiplier)| *bytes != 0 && bytes % multiplier == 0);
+
+        match unit {
+            Some((name, multiplier)) => {
+                serializer.collect_str(&format_args!("{}{}", bytes / multiplier, name))
+            }
+            None => serializer.collect_str(&format_args!("{}B", bytes)),
+        }
+    }
+
+    /// Deserializes a byte count from a human-readable string, e.g. `"10MB"`.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_str(ByteSizeVisitor)
+    }
+
+    struct ByteSizeVisitor;
+
+    impl de::Visitor<'_> for ByteSizeVisitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a byte size like \"512B\" or \"10MB\"")
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+            parse(value).map_err(|message| {
+                E::custom(format!(
+                    "invalid byte size \"{}\": {}",
+                    value.trim(),
+                    message
+                ))
+            })
+        }
+    }
+
+    fn parse(value: &str) -> Result<u64, &'static str> {
+        let value = value.trim();
+        let number_length = value
+            .find(|character: char| !character.is_ascii_digit() && character != '.')
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(number_length);
+
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err("expected a number");
+        }
+        if fraction.contains('.') {
+            return Err("expected a single decimal point");
+        }
+
+        let unit = unit.trim().to_ascii_lowercase();
+        let unit = unit.strip_suffix('b').unwrap_or(&unit);
+        let multiplier = match unit {
+            "" => 1,
+            unit => UNITS
+                .iter()
+                .find(|(name, _)| name[..name.len() - 1].eq_ignore_ascii_case(unit))
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or("unknown unit")?,
+        };
+
+        let whole = match whole {
+            "" => 0,
+            whole => whole.parse::<u128>().map_err(|_| "number is too large")?,
+        };
+        let fraction_bytes = match fraction.trim_end_matches('0') {
+            "" => 0,
+            fraction => {
+                let denominator = 10u128
+                    .checked_pow(fraction.len() as u32)
+                    .ok_or("too many decimal places")?;
+                let numerator = fraction
+                    .parse::<u128>()
+                    .ok()
+                    .and_then(|fraction| fraction.checked_mul(multiplier as u128))
+                    .ok_or("too many decimal places")?;
+                if numerator % denominator != 0 {
+                    return Err("not a whole number of bytes");
+                }
+                numerator / denominator
+            }
+        };
+
+        whole
+            .checked_mul(multiplier as u128)
+            .and_then(|bytes| bytes.checked_add(fraction_bytes))
+            .and_then(|bytes| u64::try_from(bytes).ok())
+            .ok_or("number is too large")
+    }
+}
@@ -0,0 +1,61 @@
+use std::io;
+
+use lum_libs::serde::{Deserialize, Serialize};
+
+use crate::{ConfigSaveError, FileConfigParseError};
+
+/// A trait that defines a persistent storage for a configuration of type `Config`.
+///
+/// This is implemented by [crate::FileHandler] and [crate::InMemoryHandler],
+/// and allows writing generic code against "some config store", e.g. [crate::load_from_store].
+/// Implement it to add custom persistence backends.
+///
+/// The trait is object safe, so it can be used as `&dyn ConfigStore<Config>`.
+pub trait ConfigStore<Config>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+{
+    /// Loads the configuration from the store.
+    ///
+    /// If the store does not contain a configuration yet, it should be bootstrapped with the defaults of `Config`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    fn load(&self) -> Result<Config, FileConfigParseError>;
+
+    /// Saves the configuration to the store, overwriting any existing configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to be saved.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    fn save(&self, config: &Config) -> Result<(), ConfigSaveError>;
+
+    /// Checks whether the store contains a configuration.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing whether a configuration exists.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn exists(&self) -> Result<bool, io::Error>;
+
+    /// Deletes the configuration from the store.
+    ///
+    /// Deleting a configuration that does not exist is not an error.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn delete(&self) -> Result<(), io::Error>;
+}
@@ -0,0 +1,40 @@
+use lum_libs::serde::{Deserialize, Serialize};
+
+/// A marker trait for configuration types, which is implemented for every type that implements `Serialize` and `Deserialize`.
+///
+/// Use it as a shorthand for the bounds required by the handlers of this crate:
+/// `Config: ConfigType` implies `Config: Serialize + for<'de> Deserialize<'de>`.
+///
+/// The trait is sealed, so it can not be implemented manually.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::{ConfigType, InMemoryHandler};
+///
+/// fn save_default<Config: ConfigType + Default>(handler: &InMemoryHandler<Config>) {
+///     handler.save_config(&Config::default()).unwrap();
+/// }
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Config {
+///     key: String,
+/// }
+///
+/// let handler = InMemoryHandler::<Config>::new();
+/// save_default(&handler);
+///
+/// assert!(handler.document().is_some());
+/// ```
+pub trait ConfigType: Serialize + for<'de> Deserialize<'de> + sealed::Sealed {}
+
+impl<Config> ConfigType for Config where Config: Serialize + for<'de> Deserialize<'de> {}
+
+mod sealed {
+    use lum_libs::serde::{Deserialize, Serialize};
+
+    pub trait Sealed {}
+
+    impl<Config> Sealed for Config where Config: Serialize + for<'de> Deserialize<'de> {}
+}
@@ -1,11 +1,19 @@
-use std::marker::PhantomData;
+use std::{
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    env,
+    io::{BufRead, BufReader, Read, Write},
+    marker::PhantomData,
+    str::ParseBoolError,
+};
 
 use lum_libs::{
-    serde::{Deserialize, Serialize},
+    log::warn,
+    serde::{de::Error as _, Deserialize, Serialize},
     serde_env,
+    serde_json::{self, Map, Value},
 };
 
-use crate::EnvironmentConfigParseError;
+use crate::{merger, EnvHandlerBuildError, EnvironmentConfigParseError};
 
 /// A handler for loading configuration from environment variables.
 ///
@@ -19,6 +27,13 @@ use crate::EnvironmentConfigParseError;
 /// ## Fields
 ///
 /// * `app_name` - The name of the application.
+/// * `prefix` - An explicit prefix, see [EnvHandler::with_prefix].
+/// * `additional_prefixes` - Fallback prefixes, see [EnvHandler::with_additional_prefix].
+/// * `array_indices` - Whether indexed variables are collected into sequences, see [EnvHandler::with_array_indices].
+/// * `flexible_bools` - Whether common boolean spellings like `yes` and `off` are accepted, see [EnvHandler::with_flexible_bools].
+/// * `case_insensitive` - Whether variable names are matched regardless of case, see [EnvHandler::with_case_insensitive].
+/// * `tagged_enums` - Whether variables of fields missing in the default are collected, see [EnvHandler::with_tagged_enums].
+/// * `required` - The variables that have to be set, without the prefix, see [EnvHandler::require].
 ///
 /// # Examples
 ///
@@ -45,6 +60,13 @@ where
     Config: Serialize + for<'de> Deserialize<'de>,
 {
     pub app_name: String,
+    pub prefix: Option<String>,
+    pub additional_prefixes: Vec<String>,
+    pub array_indices: bool,
+    pub flexible_bools: bool,
+    pub case_insensitive: bool,
+    pub tagged_enums: bool,
+    pub required: Vec<String>,
     _phantom_file: PhantomData<Config>,
 }
 
@@ -56,7 +78,7 @@ where
     ///
     /// # Parameters
     ///
-    /// * `app_name` - The name of the application. This will be used as a prefix for the environment variables.
+    /// * `app_name` - The name of the application. This will be used to derive the prefix for the environment variables, see [EnvHandler::resolved_prefix].
     ///
     /// # Returns
     ///
@@ -64,21 +86,1503 @@ where
     pub fn new<IntoString: Into<String>>(app_name: IntoString) -> Self {
         EnvHandler {
             app_name: app_name.into(),
+            prefix: None,
+            additional_prefixes: Vec::new(),
+            array_indices: false,
+            flexible_bools: false,
+            case_insensitive: false,
+            tagged_enums: false,
+            required: Vec::new(),
             _phantom_file: PhantomData,
         }
     }
 
+    /// Creates a new [EnvHandlerBuilder] with the given application name, to configure an `EnvHandler` with validation.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_name` - The name of the application.
+    ///
+    /// # Returns
+    ///
+    /// A new `EnvHandlerBuilder` instance.
+    pub fn builder<IntoString: Into<String>>(app_name: IntoString) -> EnvHandlerBuilder<Config> {
+        EnvHandlerBuilder::new(app_name)
+    }
+
+    /// Sets the prefix for the environment variables explicitly, instead of deriving it from `app_name`.
+    ///
+    /// The prefix is used as it is, without any sanitization.
+    ///
+    /// # Parameters
+    ///
+    /// * `prefix` - The prefix, without the trailing `_`.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the prefix set.
+    pub fn with_prefix<IntoString: Into<String>>(mut self, prefix: IntoString) -> Self {
+        self.prefix = Some(prefix.into());
+        self
+    }
+
+    /// Returns the prefix used for the environment variables, without the trailing `_`.
+    ///
+    /// This is the prefix set by [EnvHandler::with_prefix] if there is one.
+    /// Otherwise, it is derived from `app_name` by uppercasing it and replacing every character
+    /// that is not an ASCII letter or digit with `_`. For example, `"Lum Bot"` becomes `LUM_BOT`,
+    /// and `"my-app"` becomes `MY_APP`.
+    ///
+    /// If [EnvHandler::with_case_insensitive] is enabled, an explicit prefix is uppercased as well.
+    pub fn resolved_prefix(&self) -> String {
+        match &self.prefix {
+            Some(prefix) if self.case_insensitive => prefix.to_ascii_uppercase(),
+            Some(prefix) => prefix.clone(),
+            None => sanitize_prefix(&self.app_name),
+        }
+    }
+
+    /// Adds a fallback prefix, e.g. to keep honoring the environment variables of an application's former name.
+    ///
+    /// Like the application name, the prefix is sanitized (see [EnvHandler::resolved_prefix]).
+    /// Prefixes are resolved in the following order, where the first one that defines a variable wins:
+    /// 1. The prefix of [EnvHandler::resolved_prefix].
+    /// 2. The additional prefixes, in the order they were added.
+    ///
+    /// Every variable found under an additional prefix is logged as a deprecation warning,
+    /// whether it is used or shadowed by a variable of a higher priority prefix.
+    ///
+    /// # Parameters
+    ///
+    /// * `prefix` - The fallback prefix, without the trailing `_`.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the prefix added.
+    pub fn with_additional_prefix<IntoString: Into<String>>(mut self, prefix: IntoString) -> Self {
+        self.additional_prefixes
+            .push(sanitize_prefix(&prefix.into()));
+        self
+    }
+
+    /// Enables or disables collecting indexed environment variables into sequences.
+    ///
+    /// Sequences are read from a single variable with comma-separated values, e.g. `MYAPP_HOSTS=a,b`.
+    /// When enabled, variables whose name ends with `_` followed by a decimal index are collected into such a variable as well,
+    /// so `MYAPP_HOSTS_0=a` and `MYAPP_HOSTS_1=b` deserialize into `hosts: vec!["a", "b"]`.
+    ///
+    /// The naming scheme and its rules are:
+    /// * A variable is indexed if its last `_`-separated segment consists of digits only, e.g. `MYAPP_HOSTS_0` or `MYAPP_DATABASE_REPLICAS_12`.
+    /// * Elements are ordered by their numeric index, not by the order in which they were defined. `_10` comes after `_2`.
+    /// * Indices do not have to be contiguous. Missing indices are skipped, so `_0` and `_5` result in a sequence of two elements.
+    /// * If the non-indexed variable (e.g. `MYAPP_HOSTS`) is defined as well, it takes precedence and the indexed variables are ignored.
+    ///
+    /// As elements are joined with commas, elements must not contain commas themselves.
+    /// Only sequences of scalar values are supported. Sequences of structs (e.g. `MYAPP_SERVERS_0_HOST`) can not be represented
+    /// by the underlying `serde_env` deserializer, and result in an error.
+    ///
+    /// # Parameters
+    ///
+    /// * `array_indices` - Whether indexed variables should be collected into sequences.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the option applied.
+    pub fn with_array_indices(mut self, array_indices: bool) -> Self {
+        self.array_indices = array_indices;
+        self
+    }
+
+    /// Enables or disables accepting common spellings of booleans, besides `true` and `false`.
+    ///
+    /// Operators often set boolean variables like `MYAPP_DEBUG=1` or `MYAPP_DEBUG=on`, which serde does not accept.
+    /// When enabled, the following values are accepted for boolean fields, ignoring case and surrounding whitespace:
+    /// * `true`, `1`, `yes`, `y`, `on`, `enable` and `enabled` for `true`.
+    /// * `false`, `0`, `no`, `n`, `off`, `disable` and `disabled` for `false`.
+    ///
+    /// Values are only normalized for variables that fail to deserialize, so string and number fields keep their values as they are.
+    /// Any other value of a boolean field still results in `EnvironmentConfigParseError::InvalidVariable`.
+    ///
+    /// # Parameters
+    ///
+    /// * `flexible_bools` - Whether common spellings of booleans should be accepted.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the option applied.
+    pub fn with_flexible_bools(mut self, flexible_bools: bool) -> Self {
+        self.flexible_bools = flexible_bools;
+        self
+    }
+
+    /// Enables or disables matching the names of environment variables regardless of case.
+    ///
+    /// When enabled, the names of all environment variables are uppercased before matching them against the prefix,
+    /// so `MyApp_Key`, `MYAPP_KEY` and `myapp_key` all set the field `key`.
+    /// If several variables only differ in case, the one written in uppercase wins, otherwise the first one in lexicographical order.
+    /// A warning is logged for every variable that is ignored this way.
+    ///
+    /// Environment variables are case-insensitive on Windows anyway, but on Unix they are case-sensitive,
+    /// so enabling this changes which variables are read. It is therefore disabled by default.
+    ///
+    /// # Parameters
+    ///
+    /// * `case_insensitive` - Whether variable names should be matched regardless of case.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the option applied.
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Enables or disables collecting variables of fields that are missing in the default, to support tagged enums
+    /// in [EnvHandler::load_config_flattened] and [EnvHandler::load_sparse].
+    ///
+    /// Tagged enums can not be loaded by [EnvHandler::load_config], as the underlying `serde_env` deserializer does not support them.
+    /// The `Value`-based loading only knows the fields of the variant used by `Config::default()`, so the fields of the other variants are ignored.
+    /// When enabled, every variable below an object of the default that does not belong to one of its fields is added to the object as well,
+    /// named after the rest of the variable name in lowercase, so `MYAPP_BACKEND_URL` adds the field `url` to the object `backend`.
+    /// As their type is unknown, these variables are parsed as JSON if possible, otherwise used as strings,
+    /// so a string field with a numeric value has to be quoted, e.g. `MYAPP_BACKEND_PASSWORD='"1234"'`.
+    ///
+    /// The supported enum representations are:
+    /// * Internally tagged (`#[serde(tag = "type")]`): `MYAPP_BACKEND_TYPE=redis` selects the variant, `MYAPP_BACKEND_URL` sets its field.
+    /// * Adjacently tagged (`#[serde(tag = "type", content = "config")]`): `MYAPP_BACKEND_TYPE=redis` selects the variant.
+    ///   If the default variant has fields, `MYAPP_BACKEND_CONFIG_URL` sets a field of the content.
+    ///   Otherwise, the content has to be set as JSON, e.g. `MYAPP_BACKEND_CONFIG={"url":"redis://localhost"}`.
+    /// * Untagged (`#[serde(untagged)]`): the fields are added like for internally tagged enums, and the first variant that matches them is used.
+    /// * Externally tagged (the default): only unit variants, which are plain strings, e.g. `MYAPP_MODE=fast`.
+    ///
+    /// The fields of the default variant stay in place, so they are ignored when another variant is selected, unless the enum uses `#[serde(deny_unknown_fields)]`.
+    /// As a side effect, empty maps of the default are filled from their variables as well, with lowercase keys.
+    /// [EnvHandler::unknown_variables] considers all variables below nested objects known when this is enabled.
+    ///
+    /// # Parameters
+    ///
+    /// * `tagged_enums` - Whether variables of fields missing in the default should be collected.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the option applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::serde::{Deserialize, Serialize};
+    /// use lum_config::env_handler::EnvHandler;
+    /// use std::env;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// #[serde(tag = "type", rename_all = "lowercase")]
+    /// enum Backend {
+    ///     Memory { capacity: u32 },
+    ///     Redis { url: String },
+    /// }
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Config {
+    ///     backend: Backend,
+    /// }
+    ///
+    /// impl Default for Config {
+    ///     fn default() -> Self {
+    ///         Config { backend: Backend::Memory { capacity: 100 } }
+    ///     }
+    /// }
+    ///
+    /// env::set_var("TAGGED_BACKEND_TYPE", "redis");
+    /// env::set_var("TAGGED_BACKEND_URL", "redis://localhost");
+    ///
+    /// let handler = EnvHandler::<Config>::new("Tagged").with_tagged_enums(true);
+    /// let config = handler.load_config_flattened().unwrap();
+    ///
+    /// assert!(matches!(config.backend, Backend::Redis { url } if url == "redis://localhost"));
+    /// ```
+    pub fn with_tagged_enums(mut self, tagged_enums: bool) -> Self {
+        self.tagged_enums = tagged_enums;
+        self
+    }
+
+    /// Adds variables that have to be set, e.g. secrets that must not fall back to their defaults in production.
+    ///
+    /// Every name is the path of a field without the prefix, with `.` or `_` between nested fields, e.g. `database.password` or `DATABASE_PASSWORD`.
+    /// It is uppercased and prefixed like in [EnvHandler::get], so with the prefix `MYAPP`, `database.password` requires `MYAPP_DATABASE_PASSWORD`.
+    /// A variable set under an additional prefix, in a different case (see [EnvHandler::with_case_insensitive]),
+    /// or as indexed variables (see [EnvHandler::with_array_indices]) counts as set. An empty value counts as set as well.
+    ///
+    /// The variables are checked before loading the configuration by [EnvHandler::load_config], [EnvHandler::load_config_flattened] and [EnvHandler::load_sparse].
+    /// If any of them is missing, `EnvironmentConfigParseError::MissingRequired` is returned, listing all missing variables with the prefix.
+    ///
+    /// # Parameters
+    ///
+    /// * `names` - The paths of the fields whose variables have to be set.
+    ///
+    /// # Returns
+    ///
+    /// The `EnvHandler` instance with the variables added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::serde::{Deserialize, Serialize};
+    /// use lum_config::{env_handler::EnvHandler, EnvironmentConfigParseError};
+    /// use std::env;
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// #[serde(default)]
+    /// struct Config {
+    ///     api_key: String,
+    ///     database_password: String,
+    /// }
+    ///
+    /// env::set_var("REQUIRED_API_KEY", "secret");
+    ///
+    /// let handler = EnvHandler::<Config>::new("Required").require(&["api_key", "database.password"]);
+    /// let result = handler.load_config();
+    ///
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(EnvironmentConfigParseError::MissingRequired(missing)) if missing == ["REQUIRED_DATABASE_PASSWORD"]
+    /// ));
+    /// ```
+    pub fn require(mut self, names: &[&str]) -> Self {
+        self.required.extend(
+            names
+                .iter()
+                .map(|name| name.replace('.', "_").to_ascii_uppercase()),
+        );
+        self
+    }
+
     /// Loads the configuration from the environment variables.
     ///
+    /// Structs with `#[serde(flatten)]` fields and tagged enums can not be loaded this way, as the underlying `serde_env` deserializer does not support them.
+    /// Use [EnvHandler::load_config_flattened] for those, see [EnvHandler::with_tagged_enums].
+    ///
     /// # Returns
     ///
     /// A `Result` indicating success or failure.
     /// * Success is indicated by an `Ok` value, containing the Config instance.
     /// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`.
+    ///   If the variable with an invalid value can be identified, `EnvironmentConfigParseError::InvalidVariable` is returned, naming it.
     pub fn load_config(&self) -> Result<Config, EnvironmentConfigParseError> {
-        let prefix = self.app_name.to_uppercase();
-        let config = serde_env::from_env_with_prefix(&prefix)?;
+        let prefix = self.resolved_prefix();
+        let mut variables = self.resolve_variables(&prefix, env::vars());
+        if self.array_indices {
+            variables = join_array_indices(&prefix, variables);
+        }
+        self.check_required(&prefix, &variables)?;
+        debug_event!(
+            "Loading {} environment variables with prefix {}_",
+            variables.len(),
+            prefix
+        );
+        trace_event!(
+            "Environment variables: {}",
+            variables.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+
+        #[cfg(feature = "log")]
+        let started = std::time::Instant::now();
+        let mut normalized = BTreeMap::new();
+        let config = loop {
+            let error = match serde_env::from_iter_with_prefix(variables.clone(), &prefix) {
+                Ok(config) => break config,
+                Err(error) => blame_variable::<Config>(&prefix, &variables, error),
+            };
+
+            let EnvironmentConfigParseError::InvalidVariable {
+                name,
+                value,
+                source,
+            } = error
+            else {
+                return Err(error);
+            };
+
+            match parse_flexible_bool(&value) {
+                Some(boolean) if self.flexible_bools && boolean.to_string() != value => {
+                    variables.insert(name.clone(), boolean.to_string());
+                    normalized.insert(name, value);
+                }
+                _ => {
+                    return Err(EnvironmentConfigParseError::InvalidVariable {
+                        value: normalized.remove(&name).unwrap_or(value),
+                        name,
+                        source,
+                    });
+                }
+            }
+        };
+        trace_event!("Parsed environment variables in {:?}", started.elapsed());
 
         Ok(config)
     }
+
+    /// Loads the configuration from the environment variables, supporting `#[serde(flatten)]` fields.
+    ///
+    /// Instead of deserializing the variables directly, `Config::default()` is serialized to a `serde_json::Value`,
+    /// every variable overrides the field of the same name, and the result is deserialized again.
+    /// The fields of a flattened struct belong to the surrounding struct, so the flattened field `db: DbConfig { host }`
+    /// is read from `PREFIX_HOST`, not `PREFIX_DB_HOST`. Remove `#[serde(flatten)]` to get a `PREFIX_DB_` section.
+    ///
+    /// The type of each variable is taken from its default value: strings are used as they are, numbers and booleans are parsed (honoring [EnvHandler::with_flexible_bools]),
+    /// and sequences are split at commas. Variables of fields whose default is `None` are parsed as JSON if possible, otherwise used as strings.
+    /// The same applies to the elements of sequences whose default is empty.
+    /// Only fields present in the default can be set, variables below `None` values and empty maps are ignored,
+    /// unless [EnvHandler::with_tagged_enums] is enabled.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance.
+    /// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`.
+    ///   `EnvironmentConfigParseError::InvalidVariable` is returned for numbers and booleans that can not be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::serde::{Deserialize, Serialize};
+    /// use lum_config::env_handler::EnvHandler;
+    /// use std::env;
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct DbConfig {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct Config {
+    ///     name: String,
+    ///     #[serde(flatten)]
+    ///     db: DbConfig,
+    /// }
+    ///
+    /// env::set_var("FLATTENED_NAME", "app");
+    /// env::set_var("FLATTENED_HOST", "localhost");
+    /// env::set_var("FLATTENED_PORT", "5432");
+    ///
+    /// let handler = EnvHandler::<Config>::new("Flattened");
+    /// let config = handler.load_config_flattened().unwrap();
+    ///
+    /// assert_eq!(config.name, "app");
+    /// assert_eq!(config.db.host, "localhost");
+    /// assert_eq!(config.db.port, 5432);
+    /// ```
+    pub fn load_config_flattened(&self) -> Result<Config, EnvironmentConfigParseError>
+    where
+        Config: Default,
+    {
+        let mut value = serde_json::to_value(Config::default())?;
+        merger::merge_values(&mut value, self.load_sparse()?);
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Loads only the fields of `Config` whose environment variables are set, as a sparse `serde_json::Value`.
+    ///
+    /// Unlike [EnvHandler::load_config], fields without a variable are left out instead of being filled with their defaults.
+    /// Overlaying the result onto another layer with [crate::merger::merge_values] therefore only overrides the values that were actually set,
+    /// e.g. a secret injected through the environment, without replacing the other values of the layer with defaults. See [crate::load_sparse].
+    ///
+    /// The variables are read and typed like in [EnvHandler::load_config_flattened], using `Config::default()` as the schema.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing an object with the fields whose variables are set.
+    /// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`.
+    ///   `EnvironmentConfigParseError::InvalidVariable` is returned for numbers and booleans that can not be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::{serde::{Deserialize, Serialize}, serde_json::json};
+    /// use lum_config::env_handler::EnvHandler;
+    /// use std::env;
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct Config {
+    ///     host: String,
+    ///     password: String,
+    /// }
+    ///
+    /// env::set_var("SPARSE_PASSWORD", "secret");
+    ///
+    /// let handler = EnvHandler::<Config>::new("Sparse");
+    /// let value = handler.load_sparse().unwrap();
+    ///
+    /// assert_eq!(value, json!({ "password": "secret" }));
+    /// ```
+    pub fn load_sparse(&self) -> Result<Value, EnvironmentConfigParseError>
+    where
+        Config: Default,
+    {
+        let default = serde_json::to_value(Config::default())?;
+        self.load_sparse_with_schema(&default)
+    }
+
+    /// Loads the fields whose environment variables are set like [EnvHandler::load_sparse], using `schema` instead of `Config::default()`.
+    pub(crate) fn load_sparse_with_schema(
+        &self,
+        schema: &Value,
+    ) -> Result<Value, EnvironmentConfigParseError> {
+        let prefix = self.resolved_prefix();
+        let mut variables = self.resolve_variables(&prefix, env::vars());
+        if self.array_indices {
+            variables = join_array_indices(&prefix, variables);
+        }
+        self.check_required(&prefix, &variables)?;
+        debug_event!(
+            "Loading {} environment variables with prefix {}_ through the schema",
+            variables.len(),
+            prefix
+        );
+
+        let value = collect_present_variables(
+            &prefix,
+            schema,
+            &variables,
+            self.flexible_bools,
+            self.tagged_enums,
+        )?;
+
+        Ok(value.unwrap_or_else(|| Value::Object(Map::new())))
+    }
+
+    /// Reads and parses a single environment variable on demand, without deserializing the whole `Config`.
+    ///
+    /// This is useful for large configurations of which only a few values are needed, e.g. at startup,
+    /// and does not require the variables of any other field to be set.
+    /// `key` is the path of the field, with `.` or `_` between nested fields, e.g. `database.port` or `DATABASE_PORT`.
+    /// It is uppercased and prefixed like in [EnvHandler::load_config], honoring additional prefixes, case insensitivity and array indices.
+    /// The value is parsed like a field of [EnvHandler::load_config], e.g. sequences are split at commas,
+    /// and booleans accept common spellings if [EnvHandler::with_flexible_bools] is enabled.
+    ///
+    /// # Parameters
+    ///
+    /// * `key` - The path of the field.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the parsed value, or `None` if the variable is not set.
+    /// * Failure is indicated by an `Err` value, containing `EnvironmentConfigParseError::InvalidVariable`, if the value can not be parsed into `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::serde::{Deserialize, Serialize};
+    /// use lum_config::env_handler::EnvHandler;
+    /// use std::env;
+    ///
+    /// #[derive(Serialize, Deserialize)]
+    /// struct Config {
+    ///     name: String,
+    /// }
+    ///
+    /// env::set_var("GET_DATABASE_PORT", "5432");
+    ///
+    /// let handler = EnvHandler::<Config>::new("Get");
+    ///
+    /// assert_eq!(handler.get::<u16>("database.port").unwrap(), Some(5432));
+    /// assert_eq!(handler.get::<String>("database.host").unwrap(), None);
+    /// ```
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, EnvironmentConfigParseError>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let prefix = self.resolved_prefix();
+        let mut variables = self.resolve_variables(&prefix, env::vars());
+        if self.array_indices {
+            variables = join_array_indices(&prefix, variables);
+        }
+
+        let name = format!("{}_{}", prefix, key.replace('.', "_").to_ascii_uppercase());
+        let Some(value) = variables.remove(&name) else {
+            return Ok(None);
+        };
+
+        let parse = |raw: String| {
+            serde_env::from_iter::<_, _, SingleVariable<T>>([("VALUE".to_string(), raw)])
+                .map(|single| single.value)
+        };
+        let error = match parse(value.clone()) {
+            Ok(parsed) => return Ok(Some(parsed)),
+            Err(error) => error,
+        };
+
+        match parse_flexible_bool(&value) {
+            Some(boolean) if self.flexible_bools => {
+                parse(boolean.to_string()).map(Some).map_err(|_| {
+                    EnvironmentConfigParseError::InvalidVariable {
+                        name,
+                        value,
+                        source: error,
+                    }
+                })
+            }
+            _ => Err(EnvironmentConfigParseError::InvalidVariable {
+                name,
+                value,
+                source: error,
+            }),
+        }
+    }
+
+    /// Returns the environment variables with the prefix that do not correspond to any field of `Config`, e.g. to catch typos.
+    ///
+    /// The known fields are derived by serializing `Config::default()` and flattening its keys with the naming scheme of [EnvHandler::load_config],
+    /// taking [EnvHandler::with_array_indices] into account.
+    /// Fields whose default is `None` or an empty map can not be looked into, so every variable below them is considered known.
+    ///
+    /// Variables found under an additional prefix (see [EnvHandler::with_additional_prefix]) are reported with the primary prefix.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the sorted names of the unknown variables.
+    /// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`, if the default can not be serialized.
+    pub fn unknown_variables(&self) -> Result<Vec<String>, EnvironmentConfigParseError>
+    where
+        Config: Default,
+    {
+        let prefix = self.resolved_prefix();
+        let mut variables = self.resolve_variables(&prefix, env::vars());
+        if self.array_indices {
+            variables = join_array_indices(&prefix, variables);
+        }
+
+        let mut known = KnownNames::default();
+        collect_known_names(
+            prefix.clone(),
+            serde_json::to_value(Config::default())?,
+            &mut known,
+        );
+        if self.tagged_enums {
+            let nested_objects = known
+                .objects
+                .iter()
+                .filter(|name| **name != prefix)
+                .cloned()
+                .collect::<Vec<_>>();
+            known.opaque.extend(nested_objects);
+        }
+
+        let unknown_variables = variables
+            .into_keys()
+            .filter(|name| {
+                let is_leaf = known.leaves.contains(name);
+                let is_below_opaque = known
+                    .opaque
+                    .iter()
+                    .any(|opaque| name.starts_with(&format!("{}_", opaque)));
+                !is_leaf && !is_below_opaque
+            })
+            .collect();
+
+        Ok(unknown_variables)
+    }
+
+    /// Documents every environment variable read for `Config`, e.g. to generate the environment variable section of a runbook.
+    ///
+    /// The variables are derived by serializing `Config::default()` and flattening its keys with the naming scheme of [EnvHandler::load_config].
+    /// As the Rust types are not preserved by serialization, the type of each variable is described by its default value, see [EnvVarDoc::type_name].
+    /// Fields whose default is an empty map are documented as `<NAME>_<KEY>`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the documentation of the variables, sorted by name.
+    /// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`, if the default can not be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::serde::{Deserialize, Serialize};
+    /// use lum_config::env_handler::EnvHandler;
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct Config {
+    ///     hosts: Vec<String>,
+    ///     port: u16,
+    /// }
+    ///
+    /// let handler = EnvHandler::<Config>::new("MyApp");
+    /// let variables = handler.document_variables().unwrap();
+    ///
+    /// assert_eq!(variables[0].name, "MYAPP_HOSTS");
+    /// assert_eq!(variables[0].type_name, "list");
+    /// assert_eq!(variables[1].name, "MYAPP_PORT");
+    /// assert_eq!(variables[1].type_name, "integer");
+    /// assert_eq!(variables[1].default.as_deref(), Some("0"));
+    /// ```
+    pub fn document_variables(&self) -> Result<Vec<EnvVarDoc>, EnvironmentConfigParseError>
+    where
+        Config: Default,
+    {
+        let mut variables = Vec::new();
+        collect_variable_docs(
+            self.resolved_prefix(),
+            serde_json::to_value(Config::default())?,
+            &mut variables,
+        );
+        variables.sort_by(|first, second| first.name.cmp(&second.name));
+
+        Ok(variables)
+    }
+
+    /// Returns `EnvironmentConfigParseError::MissingRequired` if any of the variables of [EnvHandler::require] is not among `variables`.
+    fn check_required(
+        &self,
+        prefix: &str,
+        variables: &BTreeMap<String, String>,
+    ) -> Result<(), EnvironmentConfigParseError> {
+        let missing: Vec<String> = self
+            .required
+            .iter()
+            .map(|name| format!("{}_{}", prefix, name))
+            .filter(|name| !variables.contains_key(name))
+            .collect();
+
+        if !missing.is_empty() {
+            return Err(EnvironmentConfigParseError::MissingRequired(missing));
+        }
+
+        Ok(())
+    }
+
+    /// Collects all variables of `prefix`, and adds the variables of the additional prefixes that are not already defined, renamed to `prefix`.
+    fn resolve_variables<Variables>(
+        &self,
+        prefix: &str,
+        variables: Variables,
+    ) -> BTreeMap<String, String>
+    where
+        Variables: IntoIterator<Item = (String, String)>,
+    {
+        let mut variables: Vec<(String, String)> = variables.into_iter().collect();
+        if self.case_insensitive {
+            variables = uppercase_names(variables);
+        }
+        let primary_prefix = format!("{}_", prefix);
+
+        let mut resolved: BTreeMap<String, String> = variables
+            .iter()
+            .filter(|(key, _)| key.starts_with(&primary_prefix))
+            .cloned()
+            .collect();
+
+        for additional_prefix in &self.additional_prefixes {
+            let additional_prefix = format!("{}_", additional_prefix);
+
+            for (key, value) in &variables {
+                let Some(suffix) = key.strip_prefix(&additional_prefix) else {
+                    continue;
+                };
+
+                let name = format!("{}{}", primary_prefix, suffix);
+                match resolved.entry(name) {
+                    Entry::Occupied(entry) => {
+                        warn!(
+                            "Environment variable {} is deprecated and shadowed by {}",
+                            key,
+                            entry.key()
+                        );
+                    }
+                    Entry::Vacant(entry) => {
+                        warn!(
+                            "Environment variable {} is deprecated, use {} instead",
+                            key,
+                            entry.key()
+                        );
+                        entry.insert(value.clone());
+                    }
+                }
+            }
+        }
+
+        resolved
+    }
+}
+
+/// The documentation of an environment variable read by an [EnvHandler], see [EnvHandler::document_variables].
+///
+/// # Fields
+///
+/// * `name` - The full name of the variable, including the prefix.
+/// * `type_name` - The type of the variable, derived from its default value: `string`, `boolean`, `integer`, `float`, `list`, `map`, or `optional` for `None` defaults.
+/// * `default` - The default value, formatted like [to_env_string] does, or `None` if there is no default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVarDoc {
+    pub name: String,
+    pub type_name: &'static str,
+    pub default: Option<String>,
+}
+
+/// A builder for [EnvHandler], which validates the combination of options in [EnvHandlerBuilder::build].
+///
+/// Use [EnvHandler::new] for the default options. The setters of the builder correspond to the `with_*` methods of [EnvHandler].
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::EnvHandler;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     key: String,
+/// }
+///
+/// let handler = EnvHandler::<Config>::builder("MyApp")
+///     .prefix("MY_APP")
+///     .additional_prefix("MyOldApp")
+///     .array_indices(true)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(handler.resolved_prefix(), "MY_APP");
+/// assert_eq!(handler.additional_prefixes, vec!["MYOLDAPP"]);
+///
+/// let result = EnvHandler::<Config>::builder("MyApp").prefix("").build();
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug)]
+pub struct EnvHandlerBuilder<Config>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+{
+    handler: EnvHandler<Config>,
+}
+
+impl<Config> EnvHandlerBuilder<Config>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+{
+    /// Creates a new `EnvHandlerBuilder` with the given application name and the default options.
+    ///
+    /// # Parameters
+    ///
+    /// * `app_name` - The name of the application.
+    ///
+    /// # Returns
+    ///
+    /// A new `EnvHandlerBuilder` instance.
+    pub fn new<IntoString: Into<String>>(app_name: IntoString) -> Self {
+        EnvHandlerBuilder {
+            handler: EnvHandler::new(app_name),
+        }
+    }
+
+    /// Sets the prefix for the environment variables explicitly, see [EnvHandler::with_prefix].
+    pub fn prefix<IntoString: Into<String>>(mut self, prefix: IntoString) -> Self {
+        self.handler = self.handler.with_prefix(prefix);
+        self
+    }
+
+    /// Adds a fallback prefix, see [EnvHandler::with_additional_prefix].
+    pub fn additional_prefix<IntoString: Into<String>>(mut self, prefix: IntoString) -> Self {
+        self.handler = self.handler.with_additional_prefix(prefix);
+        self
+    }
+
+    /// Enables or disables collecting indexed environment variables into sequences, see [EnvHandler::with_array_indices].
+    pub fn array_indices(mut self, array_indices: bool) -> Self {
+        self.handler = self.handler.with_array_indices(array_indices);
+        self
+    }
+
+    /// Enables or disables accepting common spellings of booleans, see [EnvHandler::with_flexible_bools].
+    pub fn flexible_bools(mut self, flexible_bools: bool) -> Self {
+        self.handler = self.handler.with_flexible_bools(flexible_bools);
+        self
+    }
+
+    /// Enables or disables matching variable names regardless of case, see [EnvHandler::with_case_insensitive].
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.handler = self.handler.with_case_insensitive(case_insensitive);
+        self
+    }
+
+    /// Enables or disables collecting variables of fields missing in the default, see [EnvHandler::with_tagged_enums].
+    pub fn tagged_enums(mut self, tagged_enums: bool) -> Self {
+        self.handler = self.handler.with_tagged_enums(tagged_enums);
+        self
+    }
+
+    /// Adds variables that have to be set, see [EnvHandler::require].
+    pub fn require(mut self, names: &[&str]) -> Self {
+        self.handler = self.handler.require(names);
+        self
+    }
+
+    /// Validates the options and builds the `EnvHandler`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the `EnvHandler` instance.
+    /// * Failure is indicated by an `Err` value, containing an `EnvHandlerBuildError` if the resolved prefix
+    ///   or an additional prefix is empty, or if an additional prefix equals the resolved prefix.
+    pub fn build(self) -> Result<EnvHandler<Config>, EnvHandlerBuildError> {
+        let prefix = self.handler.resolved_prefix();
+        if prefix.is_empty() {
+            return Err(EnvHandlerBuildError::EmptyPrefix);
+        }
+
+        for additional_prefix in &self.handler.additional_prefixes {
+            if additional_prefix.is_empty() {
+                return Err(EnvHandlerBuildError::EmptyPrefix);
+            }
+            if *additional_prefix == prefix {
+                return Err(EnvHandlerBuildError::DuplicatePrefix(prefix));
+            }
+        }
+
+        Ok(self.handler)
+    }
+}
+
+/// A single variable deserialized by [EnvHandler::get].
+#[derive(Deserialize)]
+struct SingleVariable<T> {
+    value: T,
+}
+
+/// The variable names of the fields of a configuration, see [EnvHandler::unknown_variables].
+#[derive(Default)]
+struct KnownNames {
+    /// Names of fields holding a value.
+    leaves: BTreeSet<String>,
+    /// Names of fields whose nested fields are unknown, i.e. `None` values and empty maps.
+    opaque: BTreeSet<String>,
+    /// Names of fields holding a non-empty object, including the configuration itself.
+    objects: BTreeSet<String>,
+}
+
+/// Collects the variable names of all fields of `value`, naming nested fields `<name>_<KEY>`.
+fn collect_known_names(name: String, value: Value, known: &mut KnownNames) {
+    match value {
+        Value::Object(object) if object.is_empty() => {
+            known.opaque.insert(name);
+        }
+        Value::Object(object) => {
+            known.objects.insert(name.clone());
+            for (key, value) in object {
+                collect_known_names(
+                    format!("{}_{}", name, key.to_ascii_uppercase()),
+                    value,
+                    known,
+                );
+            }
+        }
+        Value::Null => {
+            known.opaque.insert(name.clone());
+            known.leaves.insert(name);
+        }
+        _ => {
+            known.leaves.insert(name);
+        }
+    }
+}
+
+/// Collects the values of the fields of `default` whose variables are set, naming nested fields `<name>_<KEY>`.
+///
+/// If `tagged_enums` is set, variables below an object that do not belong to one of its fields are added to it as well, see [EnvHandler::with_tagged_enums].
+///
+/// Returns `None` if no variable of `default` or its nested fields is set.
+fn collect_present_variables(
+    name: &str,
+    default: &Value,
+    variables: &BTreeMap<String, String>,
+    flexible_bools: bool,
+    tagged_enums: bool,
+) -> Result<Option<Value>, EnvironmentConfigParseError> {
+    if let Value::Object(object) = default {
+        let mut present = Map::new();
+        for (key, nested) in object {
+            let nested_name = format!("{}_{}", name, key.to_ascii_uppercase());
+            if let Some(value) = collect_present_variables(
+                &nested_name,
+                nested,
+                variables,
+                flexible_bools,
+                tagged_enums,
+            )? {
+                present.insert(key.clone(), value);
+            }
+        }
+
+        if tagged_enums {
+            let object_prefix = format!("{}_", name);
+            for (variable, raw) in variables.range(object_prefix.clone()..) {
+                let Some(rest) = variable.strip_prefix(&object_prefix) else {
+                    break;
+                };
+                let belongs_to_field = object.keys().any(|key| {
+                    let key = key.to_ascii_uppercase();
+                    rest == key || rest.starts_with(&format!("{}_", key))
+                });
+                if rest.is_empty() || belongs_to_field {
+                    continue;
+                }
+
+                let value =
+                    parse_variable(raw, &Value::Null, flexible_bools).map_err(|message| {
+                        EnvironmentConfigParseError::InvalidVariable {
+                            name: variable.clone(),
+                            value: raw.clone(),
+                            source: serde_env::Error::custom(message),
+                        }
+                    })?;
+                present.insert(rest.to_ascii_lowercase(), value);
+            }
+        }
+
+        return Ok((!present.is_empty()).then_some(Value::Object(present)));
+    }
+
+    let Some(raw) = variables.get(name) else {
+        return Ok(None);
+    };
+    parse_variable(raw, default, flexible_bools)
+        .map(Some)
+        .map_err(|message| EnvironmentConfigParseError::InvalidVariable {
+            name: name.to_string(),
+            value: raw.clone(),
+            source: serde_env::Error::custom(message),
+        })
+}
+
+/// Parses the value `raw` of a variable, using the type of `default`.
+fn parse_variable(raw: &str, default: &Value, flexible_bools: bool) -> Result<Value, String> {
+    match default {
+        Value::Bool(_) if flexible_bools => parse_flexible_bool(raw)
+            .map(Value::Bool)
+            .ok_or_else(|| "expected a boolean like `true`, `yes` or `off`".to_string()),
+        Value::Bool(_) => raw
+            .parse()
+            .map(Value::Bool)
+            .map_err(|error: ParseBoolError| error.to_string()),
+        Value::Number(_) => raw
+            .parse()
+            .map(Value::Number)
+            .map_err(|error: serde_json::Error| error.to_string()),
+        Value::Array(_) if raw.is_empty() => Ok(Value::Array(Vec::new())),
+        Value::Array(elements) => {
+            let element_default = elements.first().unwrap_or(&Value::Null);
+            raw.split(',')
+                .map(|element| parse_variable(element, element_default, flexible_bools))
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        }
+        Value::Null => {
+            Ok(serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string())))
+        }
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// Collects the documentation of all fields of `value`, naming nested fields `<name>_<KEY>`.
+fn collect_variable_docs(name: String, value: Value, variables: &mut Vec<EnvVarDoc>) {
+    let (type_name, default) = match value {
+        Value::Object(object) if object.is_empty() => {
+            variables.push(EnvVarDoc {
+                name: format!("{}_<KEY>", name),
+                type_name: "map",
+                default: None,
+            });
+            return;
+        }
+        Value::Object(object) => {
+            for (key, value) in object {
+                collect_variable_docs(
+                    format!("{}_{}", name, key.to_ascii_uppercase()),
+                    value,
+                    variables,
+                );
+            }
+            return;
+        }
+        Value::Null => ("optional", None),
+        Value::Bool(boolean) => ("boolean", Some(boolean.to_string())),
+        Value::Number(number) if number.is_f64() => ("float", Some(number.to_string())),
+        Value::Number(number) => ("integer", Some(number.to_string())),
+        Value::String(string) => ("string", Some(string)),
+        Value::Array(array) => {
+            let elements: Vec<String> = array.into_iter().filter_map(scalar_to_string).collect();
+            ("list", Some(elements.join(",")))
+        }
+    };
+
+    variables.push(EnvVarDoc {
+        name,
+        type_name,
+        default,
+    });
+}
+
+/// Uppercases the names of `variables`, keeping a single variable of each name, see [EnvHandler::with_case_insensitive].
+fn uppercase_names(mut variables: Vec<(String, String)>) -> Vec<(String, String)> {
+    // Put names written in uppercase first, so they win over all other spellings
+    variables.sort_by(|(first, _), (second, _)| {
+        let first_is_uppercase = *first == first.to_ascii_uppercase();
+        let second_is_uppercase = *second == second.to_ascii_uppercase();
+        second_is_uppercase
+            .cmp(&first_is_uppercase)
+            .then_with(|| first.cmp(second))
+    });
+
+    let mut uppercased: BTreeMap<String, (String, String)> = BTreeMap::new();
+    for (name, value) in variables {
+        match uppercased.entry(name.to_ascii_uppercase()) {
+            Entry::Occupied(entry) => {
+                warn!(
+                    "Environment variable {} is shadowed by {}, which only differs in case",
+                    name,
+                    entry.get().0
+                );
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((name, value));
+            }
+        }
+    }
+
+    uppercased
+        .into_iter()
+        .map(|(name, (_, value))| (name, value))
+        .collect()
+}
+
+/// Parses the common spellings of booleans accepted by [EnvHandler::with_flexible_bools].
+fn parse_flexible_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" | "on" | "enable" | "enabled" => Some(true),
+        "false" | "0" | "no" | "n" | "off" | "disable" | "disabled" => Some(false),
+        _ => None,
+    }
+}
+
+/// Serializes `config` to `PREFIX_KEY=value` lines, e.g. to generate a `.env` file, using the naming scheme [EnvHandler::load_config] reads.
+///
+/// Keys of nested structs are joined with `_` and uppercased, so the field `database.user` becomes `PREFIX_DATABASE_USER`.
+/// Sequences of scalar values are joined with commas. `None` values are omitted. The lines are sorted by name.
+/// Values are written as they are, without quoting.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to be serialized.
+/// * `prefix` - The prefix of the environment variables, without the trailing `_`, e.g. the result of [EnvHandler::resolved_prefix].
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the lines, each terminated by a newline.
+/// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`. `EnvironmentConfigParseError::UnsupportedValue`
+///   is returned for values that can not be read back, i.e. sequences of non-scalar values, sequence elements containing commas, and values containing newlines.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::Serialize;
+/// use lum_config::env_handler::to_env_string;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     user: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     hosts: Vec<String>,
+///     database: Database,
+/// }
+///
+/// let config = Config {
+///     hosts: vec!["a".to_string(), "b".to_string()],
+///     database: Database {
+///         user: "lum".to_string(),
+///     },
+/// };
+///
+/// let output = to_env_string(&config, "MYAPP").unwrap();
+/// assert_eq!(output, "MYAPP_DATABASE_USER=lum\nMYAPP_HOSTS=a,b\n");
+/// ```
+pub fn to_env_string<Config: Serialize>(
+    config: &Config,
+    prefix: &str,
+) -> Result<String, EnvironmentConfigParseError> {
+    let mut variables = BTreeMap::new();
+    flatten_value(
+        prefix.to_string(),
+        serde_json::to_value(config)?,
+        &mut variables,
+    )?;
+
+    let mut output = String::new();
+    for (name, value) in variables {
+        if value.contains(['\n', '\r']) {
+            return Err(EnvironmentConfigParseError::UnsupportedValue(name));
+        }
+        output.push_str(&format!("{}={}\n", name, value));
+    }
+
+    Ok(output)
+}
+
+/// Loads a configuration from `KEY=value` lines in the dotenv format, e.g. an env file written by [save_to_writer].
+///
+/// The variables are read from `reader` instead of the environment, and deserialized like in [EnvHandler::load_config].
+/// The format is:
+/// * Blank lines and lines starting with `#` are ignored. An optional `export ` before the name is ignored as well.
+/// * Values are trimmed, and may be followed by a comment starting with ` #`.
+/// * Values in single quotes are used as they are.
+/// * Values in double quotes support the escape sequences `\\`, `\"`, `\n`, `\r` and `\t`. A `#` within quotes does not start a comment.
+///
+/// # Arguments
+///
+/// * `reader` - The reader to read the lines from.
+/// * `prefix` - The prefix of the environment variables, without the trailing `_`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the Config instance.
+/// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`.
+///   `EnvironmentConfigParseError::InvalidLine` is returned for lines without `=` and for unterminated quotes.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::env_handler::load_from_reader;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     greeting: String,
+///     port: u16,
+/// }
+///
+/// let env_file = "# Deployment snapshot\nMYAPP_GREETING=\"Hello # World\"\nexport MYAPP_PORT=8080 # default\n";
+/// let config: Config = load_from_reader(env_file.as_bytes(), "MYAPP").unwrap();
+///
+/// assert_eq!(config.greeting, "Hello # World");
+/// assert_eq!(config.port, 8080);
+/// ```
+pub fn load_from_reader<Config, Reader>(
+    reader: Reader,
+    prefix: &str,
+) -> Result<Config, EnvironmentConfigParseError>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    Reader: Read,
+{
+    let mut variables = BTreeMap::new();
+    for (index, line) in BufReader::new(reader).lines().enumerate() {
+        if let Some((name, value)) =
+            parse_env_line(&line?).map_err(|message| EnvironmentConfigParseError::InvalidLine {
+                line: index + 1,
+                message,
+            })?
+        {
+            variables.insert(name, value);
+        }
+    }
+
+    serde_env::from_iter_with_prefix(variables.clone(), prefix)
+        .map_err(|error| blame_variable::<Config>(prefix, &variables, error))
+}
+
+/// Writes `config` as `PREFIX_KEY=value` lines to `writer`, in the format read by [load_from_reader].
+///
+/// The variables are named like in [to_env_string]. Unlike there, values that are empty or contain characters other than
+/// ASCII letters, digits and `_-.,:/@+` are written in double quotes, so values with spaces, `#` or newlines survive the round trip.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to be written.
+/// * `writer` - The writer to write the lines to.
+/// * `prefix` - The prefix of the environment variables, without the trailing `_`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the unit type `()`.
+/// * Failure is indicated by an `Err` value, containing an `EnvironmentConfigParseError`. `EnvironmentConfigParseError::UnsupportedValue`
+///   is returned for sequences of non-scalar values and sequence elements containing commas.
+pub fn save_to_writer<Config, Writer>(
+    config: &Config,
+    mut writer: Writer,
+    prefix: &str,
+) -> Result<(), EnvironmentConfigParseError>
+where
+    Config: Serialize,
+    Writer: Write,
+{
+    let mut variables = BTreeMap::new();
+    flatten_value(
+        prefix.to_string(),
+        serde_json::to_value(config)?,
+        &mut variables,
+    )?;
+
+    for (name, value) in variables {
+        writeln!(writer, "{}={}", name, quote_env_value(&value))?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Parses a line of an env file, see [load_from_reader].
+///
+/// Returns `None` for blank lines and comments.
+fn parse_env_line(line: &str) -> Result<Option<(String, String)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let line = line.strip_prefix("export ").unwrap_or(line);
+    let Some((name, value)) = line.split_once('=') else {
+        return Err("expected NAME=value".to_string());
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("missing variable name".to_string());
+    }
+
+    let value = value.trim();
+    let value = match value.chars().next() {
+        Some(quote @ ('"' | '\'')) => parse_quoted_env_value(&value[1..], quote)?,
+        _ => match value.find(" #") {
+            Some(comment) => value[..comment].trim_end().to_string(),
+            None => value.to_string(),
+        },
+    };
+
+    Ok(Some((name.to_string(), value)))
+}
+
+/// Parses the rest of a value after its opening `quote`, up to the closing quote, see [load_from_reader].
+fn parse_quoted_env_value(rest: &str, quote: char) -> Result<String, String> {
+    let mut value = String::new();
+    let mut characters = rest.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            _ if character == quote => {
+                let trailing = characters.as_str().trim_start();
+                if !trailing.is_empty() && !trailing.starts_with('#') {
+                    return Err(format!(
+                        "unexpected characters after closing quote: {trailing}"
+                    ));
+                }
+                return Ok(value);
+            }
+            '\\' if quote == '"' => match characters.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some(escaped @ ('\\' | '"')) => value.push(escaped),
+                Some(escaped) => {
+                    value.push('\\');
+                    value.push(escaped);
+                }
+                None => break,
+            },
+            _ => value.push(character),
+        }
+    }
+
+    Err(format!("missing closing quote {quote}"))
+}
+
+/// Quotes `value` for an env file if necessary, see [save_to_writer].
+fn quote_env_value(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "_-.,:/@+".contains(character));
+    if is_plain {
+        return value.to_string();
+    }
+
+    let mut quoted = String::from('"');
+    for character in value.chars() {
+        match character {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// Flattens `value` into `variables`, naming nested values `<name>_<KEY>`.
+fn flatten_value(
+    name: String,
+    value: Value,
+    variables: &mut BTreeMap<String, String>,
+) -> Result<(), EnvironmentConfigParseError> {
+    match value {
+        Value::Null => {}
+        Value::Object(object) => {
+            for (key, value) in object {
+                flatten_value(
+                    format!("{}_{}", name, key.to_ascii_uppercase()),
+                    value,
+                    variables,
+                )?;
+            }
+        }
+        Value::Array(array) => {
+            let mut elements = Vec::with_capacity(array.len());
+            for element in array {
+                match scalar_to_string(element) {
+                    Some(element) if !element.contains(',') => elements.push(element),
+                    _ => return Err(EnvironmentConfigParseError::UnsupportedValue(name)),
+                }
+            }
+            variables.insert(name, elements.join(","));
+        }
+        scalar => {
+            if let Some(scalar) = scalar_to_string(scalar) {
+                variables.insert(name, scalar);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the textual representation of a scalar `value`, or `None` for objects, arrays and null.
+fn scalar_to_string(value: Value) -> Option<String> {
+    match value {
+        Value::String(string) => Some(string),
+        Value::Bool(boolean) => Some(boolean.to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Uppercases `name` and replaces every character that is not an ASCII letter or digit with `_`.
+fn sanitize_prefix(name: &str) -> String {
+    name.chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() {
+                character.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Identifies the variable with the invalid value that caused `error`, as `serde_env` does not report it.
+///
+/// The variable is found by deserializing each variable on its own, as `serde_env` visits the fields of a struct in random order,
+/// so the error of a single variable is the only one that can be reproduced reliably. Missing fields are reported after all present fields,
+/// so any other error of a single variable is caused by its value.
+/// The variable whose error equals `error` is preferred, falling back to the first invalid variable.
+/// If `error` is caused by a missing variable, or no variable can be identified, `error` is returned as it is.
+fn blame_variable<Config>(
+    prefix: &str,
+    variables: &BTreeMap<String, String>,
+    error: serde_env::Error,
+) -> EnvironmentConfigParseError
+where
+    Config: for<'de> Deserialize<'de>,
+{
+    let message = error.to_string();
+    if message.starts_with("missing field") {
+        return error.into();
+    }
+
+    let mut invalid_variable = None;
+    for (name, value) in variables {
+        let single = [(name.clone(), value.clone())];
+        let variable_error = match serde_env::from_iter_with_prefix::<_, _, Config>(single, prefix)
+        {
+            Err(variable_error) if !variable_error.to_string().starts_with("missing field") => {
+                variable_error
+            }
+            _ => continue,
+        };
+
+        if variable_error.to_string() == message {
+            return EnvironmentConfigParseError::InvalidVariable {
+                name: name.clone(),
+                value: value.clone(),
+                source: variable_error,
+            };
+        }
+        invalid_variable.get_or_insert((name, value, variable_error));
+    }
+
+    match invalid_variable {
+        Some((name, value, source)) => EnvironmentConfigParseError::InvalidVariable {
+            name: name.clone(),
+            value: value.clone(),
+            source,
+        },
+        None => error.into(),
+    }
+}
+
+/// Collects all variables of `prefix` ending with `_<index>` into a single variable with comma-separated values, ordered by index.
+///
+/// Variables that are not indexed are kept as they are, and take precedence over indexed variables of the same name.
+fn join_array_indices(
+    prefix: &str,
+    variables: BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let minimum_length = prefix.len() + 1;
+    let mut joined = BTreeMap::new();
+    let mut sequences: BTreeMap<String, BTreeMap<u64, String>> = BTreeMap::new();
+
+    for (key, value) in variables {
+        let index = key.rsplit_once('_').and_then(|(name, index)| {
+            let is_index = !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit());
+            match (is_index && name.len() > minimum_length, index.parse()) {
+                (true, Ok(index)) => Some((name.to_string(), index)),
+                _ => None,
+            }
+        });
+
+        match index {
+            Some((name, index)) => {
+                sequences.entry(name).or_default().insert(index, value);
+            }
+            None => {
+                joined.insert(key, value);
+            }
+        }
+    }
+
+    for (name, elements) in sequences {
+        match joined.entry(name) {
+            Entry::Occupied(entry) => {
+                warn!(
+                    "Environment variable {} is defined, ignoring its indexed variables",
+                    entry.key()
+                );
+            }
+            Entry::Vacant(entry) => {
+                let elements: Vec<String> = elements.into_values().collect();
+                entry.insert(elements.join(","));
+            }
+        }
+    }
+
+    joined
 }
@@ -1,4 +1,4 @@
-use std::io;
+use std::{error::Error as StdError, io, path::PathBuf};
 
 use lum_libs::{serde_env, serde_json, thiserror::Error};
 
@@ -7,6 +7,9 @@ use lum_libs::{serde_env, serde_json, thiserror::Error};
 pub enum ConfigPathError {
     #[error("Unable to get OS-specific config directory")]
     UnknownConfigDirectory,
+
+    #[error("Unable to get the directory of the executable: {0}")]
+    UnknownExecutableDirectory(io::Error),
 }
 
 /// Error that can occur when trying to save a configuration to a file.
@@ -15,8 +18,14 @@ pub enum ConfigSaveError {
     #[error("Unable to serialize config: {0}")]
     Serde(#[from] serde_json::Error),
 
+    #[error("Unable to encode config: {0}")]
+    Codec(Box<dyn StdError + Send + Sync>),
+
     #[error("I/O error: {0}")]
     IO(#[from] io::Error),
+
+    #[error("Config path {0} exists, but is not a file")]
+    NotAFile(PathBuf),
 }
 
 /// Error that can occur when trying to parse a configuration from a file.
@@ -30,6 +39,126 @@ pub enum FileConfigParseError {
 
     #[error("Unable to serialize or deserialize config: {0}")]
     Serde(#[from] serde_json::Error),
+
+    #[error("Unable to decode config: {0}")]
+    Codec(Box<dyn StdError + Send + Sync>),
+
+    #[error("Config path {0} exists, but is not a file")]
+    NotAFile(PathBuf),
+
+    #[error("Environment variable {0} is referenced in the config, but not set")]
+    UnresolvedInterpolation(String),
+
+    #[error("Config file {path} exceeds the maximum size of {max_size} bytes")]
+    TooLarge { path: PathBuf, max_size: u64 },
+
+    #[error("Config file {0} is empty")]
+    EmptyFile(PathBuf),
+
+    #[error("No config value at path {0}")]
+    NotFound(String),
+
+    #[error("Config file {0} is included recursively")]
+    IncludeCycle(PathBuf),
+
+    #[error("Unable to load included config file {path}: {source}")]
+    Include {
+        path: PathBuf,
+        source: Box<FileConfigParseError>,
+    },
+
+    #[error("Invalid record on line {line}: {source}")]
+    InvalidRecord {
+        line: usize,
+        source: serde_json::Error,
+    },
+
+    #[error("Unable to set up created config file {path}: {source}")]
+    OnCreate {
+        path: PathBuf,
+        source: Box<dyn StdError + Send + Sync>,
+    },
+}
+
+impl FileConfigParseError {
+    /// Returns the position in the configuration file at which parsing failed, as 1-based line and column.
+    ///
+    /// The position is only known for syntax and data errors of the bundled [crate::JsonCodec], and for invalid records of [crate::file_handler::load_ndjson].
+    pub fn location(&self) -> Option<(usize, usize)> {
+        let error = match self {
+            FileConfigParseError::Codec(error) => error.downcast_ref::<serde_json::Error>()?,
+            FileConfigParseError::InvalidRecord { line, source } => {
+                return Some((*line, source.column()));
+            }
+            _ => return None,
+        };
+
+        match (error.line(), error.column()) {
+            (0, _) => None,
+            (line, column) => Some((line, column)),
+        }
+    }
+
+    /// Renders the error with the surrounding lines of the configuration file and a caret under the position at which parsing failed.
+    ///
+    /// If the position is unknown (see [FileConfigParseError::location]), only the error message is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The content of the configuration file that failed to parse.
+    ///
+    /// # Returns
+    ///
+    /// The rendered diagnostic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_config::{Codec, FileConfigParseError, JsonCodec};
+    /// use std::collections::HashMap;
+    ///
+    /// let source = "{\n  \"key\": value\n}";
+    /// let error = JsonCodec::default()
+    ///     .from_str::<HashMap<String, String>>(source)
+    ///     .unwrap_err();
+    /// let error = FileConfigParseError::Codec(Box::new(error));
+    ///
+    /// let rendered = error.render_with_source(source);
+    /// assert!(rendered.contains("2 |   \"key\": value\n  |          ^"));
+    /// ```
+    pub fn render_with_source(&self, source: &str) -> String {
+        const CONTEXT_LINES: usize = 2;
+
+        let message = self.to_string();
+        let Some((line, column)) = self.location() else {
+            return message;
+        };
+
+        let lines: Vec<&str> = source.lines().collect();
+        let first_line = line.saturating_sub(CONTEXT_LINES).max(1);
+        let last_line = (line + CONTEXT_LINES).min(lines.len());
+        let width = last_line.max(line).to_string().len();
+
+        let mut rendered = format!(
+            "{}\n{:width$}--> line {}, column {}\n{:width$} |",
+            message, "", line, column, ""
+        );
+        for number in first_line..=last_line {
+            let content = lines.get(number - 1).copied().unwrap_or_default();
+            rendered.push_str(&format!("\n{:>width$} | {}", number, content));
+
+            if number == line {
+                let offset: String = content
+                    .chars()
+                    .take(column.saturating_sub(1))
+                    .map(|character| if character == '\t' { '\t' } else { ' ' })
+                    .collect();
+                rendered.push_str(&format!("\n{:width$} | {}^", "", offset));
+            }
+        }
+
+        rendered
+    }
 }
 
 /// Error that can occur when trying to parse a configuration from environment variables.
@@ -37,6 +166,38 @@ pub enum FileConfigParseError {
 pub enum EnvironmentConfigParseError {
     #[error("Unable to parse environment variables: {0}")]
     SerdeEnv(#[from] serde_env::Error),
+
+    #[error("{name}: invalid value '{value}', {source}")]
+    InvalidVariable {
+        name: String,
+        value: String,
+        source: serde_env::Error,
+    },
+
+    #[error("Unable to serialize config: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Value of {0} can not be represented as an environment variable")]
+    UnsupportedValue(String),
+
+    #[error("I/O error: {0}")]
+    IO(#[from] io::Error),
+
+    #[error("Invalid line {line}: {message}")]
+    InvalidLine { line: usize, message: String },
+
+    #[error("Required environment variables are not set: {}", .0.join(", "))]
+    MissingRequired(Vec<String>),
+}
+
+/// Error that can occur when trying to build an [crate::EnvHandler] with an [crate::EnvHandlerBuilder].
+#[derive(Debug, Error)]
+pub enum EnvHandlerBuildError {
+    #[error("The environment variable prefix must not be empty")]
+    EmptyPrefix,
+
+    #[error("The additional prefix {0} equals the prefix of the environment variables")]
+    DuplicatePrefix(String),
 }
 
 /// Error that can occur when trying to load a configuration.
@@ -50,4 +211,91 @@ pub enum ConfigLoadError {
 
     #[error("Unable to parse file config: {0}")]
     ParseFile(#[from] FileConfigParseError),
+
+    #[error("Unable to load config from any source. Environment: {env}. File: {file}")]
+    AllSources {
+        env: EnvironmentConfigParseError,
+        file: Box<ConfigLoadError>,
+    },
+}
+
+// Exit codes of sysexits.h, see [ConfigLoadError::exit_code]
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_IOERR: i32 = 74;
+const EX_NOPERM: i32 = 77;
+const EX_CONFIG: i32 = 78;
+
+impl ConfigLoadError {
+    /// Returns a process exit code for the error, following the conventions of `sysexits.h`.
+    ///
+    /// The codes are stable:
+    /// * `65` (`EX_DATAERR`) - The environment variables or the configuration file could not be parsed.
+    /// * `66` (`EX_NOINPUT`) - The configuration file could not be found, or is not a file.
+    /// * `74` (`EX_IOERR`) - Any other IO error while reading or writing the configuration file.
+    /// * `77` (`EX_NOPERM`) - Missing permissions to read or write the configuration file or directory.
+    /// * `78` (`EX_CONFIG`) - The configuration directory could not be determined, i.e. for every [ConfigLoadError::Path].
+    ///
+    /// For [ConfigLoadError::AllSources], the code of the file error is returned.
+    pub fn exit_code(&self) -> i32 {
+        if let Some(error) = self.io_error() {
+            return match error.kind() {
+                io::ErrorKind::NotFound => EX_NOINPUT,
+                io::ErrorKind::PermissionDenied => EX_NOPERM,
+                _ => EX_IOERR,
+            };
+        }
+
+        match self {
+            ConfigLoadError::Path(_) => EX_CONFIG,
+            ConfigLoadError::AllSources { file, .. } => file.exit_code(),
+            ConfigLoadError::ParseFile(FileConfigParseError::NotAFile(_))
+            | ConfigLoadError::ParseFile(FileConfigParseError::Save(ConfigSaveError::NotAFile(
+                _,
+            ))) => EX_NOINPUT,
+            _ => EX_DATAERR,
+        }
+    }
+
+    /// Returns the underlying IO error, if the configuration file could not be read or written.
+    fn io_error(&self) -> Option<&io::Error> {
+        match self {
+            ConfigLoadError::ParseFile(FileConfigParseError::IO(error))
+            | ConfigLoadError::ParseFile(FileConfigParseError::Save(ConfigSaveError::IO(error))) => {
+                Some(error)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the `io::ErrorKind` of the error when converted into an `io::Error`.
+    fn io_error_kind(&self) -> io::ErrorKind {
+        match self {
+            ConfigLoadError::Path(ConfigPathError::UnknownExecutableDirectory(error)) => {
+                error.kind()
+            }
+            ConfigLoadError::Path(ConfigPathError::UnknownConfigDirectory) => io::ErrorKind::Other,
+            ConfigLoadError::ParseEnv(_) => io::ErrorKind::InvalidData,
+            ConfigLoadError::ParseFile(FileConfigParseError::IO(error))
+            | ConfigLoadError::ParseFile(FileConfigParseError::Save(ConfigSaveError::IO(error))) => {
+                error.kind()
+            }
+            ConfigLoadError::ParseFile(FileConfigParseError::NotAFile(_))
+            | ConfigLoadError::ParseFile(FileConfigParseError::Save(ConfigSaveError::NotAFile(
+                _,
+            ))) => io::ErrorKind::Other,
+            ConfigLoadError::ParseFile(_) => io::ErrorKind::InvalidData,
+            ConfigLoadError::AllSources { file, .. } => file.io_error_kind(),
+        }
+    }
+}
+
+/// Converts the error into an `io::Error`, e.g. to return it from `fn main() -> io::Result<()>`.
+///
+/// The kind of an underlying IO error is kept. Parse errors become `io::ErrorKind::InvalidData`, and all other errors `io::ErrorKind::Other`.
+/// The message of the `io::Error` is the message of the `ConfigLoadError`.
+impl From<ConfigLoadError> for io::Error {
+    fn from(error: ConfigLoadError) -> Self {
+        io::Error::new(error.io_error_kind(), error)
+    }
 }
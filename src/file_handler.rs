@@ -1,12 +1,29 @@
-use std::{fs, io, marker::PhantomData, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env,
+    error::Error as StdError,
+    ffi::OsString,
+    fmt,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Lines, Read, Write},
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, SystemTime},
+};
 
 use lum_libs::{
     dirs,
+    log::warn,
     serde::{Deserialize, Serialize},
-    serde_json,
+    serde_json::{self, Map, Value},
 };
 
-use crate::{ConfigPathError, ConfigSaveError, FileConfigParseError};
+use crate::{
+    file_system::{FileMetadata, FileSystem, StdFs},
+    merger, Codec, ConfigPathError, ConfigSaveError, ConfigStore, FileConfigParseError, JsonCodec,
+    JsonStyle, RetryPolicy,
+};
 
 /// A handler for loading and saving configuration from/to files.
 ///
@@ -16,6 +33,8 @@ use crate::{ConfigPathError, ConfigSaveError, FileConfigParseError};
 /// # Type Parameters
 ///
 /// * `Config` - The configuration type that implements `Serialize` and `Deserialize`. This is the type to which the configuration file will be deserialized.
+/// * `FileCodec` - The [Codec] used to read and write the configuration file. Defaults to [JsonCodec]. See [FileHandler::with_codec].
+/// * `FileStorage` - The [FileSystem] used to access the configuration file. Defaults to [StdFs]. See [FileHandler::with_file_system].
 ///
 /// # Fields
 ///
@@ -56,15 +75,274 @@ use crate::{ConfigPathError, ConfigSaveError, FileConfigParseError};
 /// assert_eq!(config.key, "default_value");
 /// ```
 #[derive(Debug)]
-pub struct FileHandler<Config>
+pub struct FileHandler<Config, FileCodec = JsonCodec, FileStorage = StdFs>
 where
     Config: Serialize + for<'de> Deserialize<'de>,
+    FileCodec: Codec,
+    FileStorage: FileSystem,
 {
     pub config_directory_path: PathBuf,
     pub config_file_path: PathBuf,
+    codec: FileCodec,
+    file_system: FileStorage,
+    options: FileHandlerOptions,
+    unknown_keys: Mutex<Map<String, Value>>,
     _phantom_file: PhantomData<Config>,
 }
 
+/// Information about a corrupt configuration file that was replaced by [FileHandler::load_config_recover].
+///
+/// # Fields
+///
+/// * `corrupt_file_path` - The path the corrupt configuration file was moved to.
+/// * `error` - The error that occurred while parsing the corrupt configuration file.
+#[derive(Debug)]
+pub struct RecoveryInfo {
+    pub corrupt_file_path: PathBuf,
+    pub error: FileConfigParseError,
+}
+
+/// The keys that were present in a configuration file loaded by [FileHandler::load_config_with_presence].
+///
+/// Paths are written with `.` between nested keys, e.g. `database.user`.
+/// Only keys of the configuration type are recorded. Keys that are unknown to it are not.
+///
+/// A key of a nested object is present if the object is present and contains it.
+/// If a nested object is present, but some of its keys are not, these keys were filled in from the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PresenceMap {
+    paths: BTreeSet<String>,
+}
+
+impl PresenceMap {
+    /// Returns whether the key at `path` was present in the configuration file.
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Returns the paths of all keys that were present in the configuration file, in sorted order.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.paths.iter().map(String::as_str)
+    }
+}
+
+/// Defines how strictly [FileHandler::load_config] treats a missing or invalid configuration file, see [FileHandler::with_strictness].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// A missing configuration file is an error, and is never created.
+    Strict,
+    /// A missing configuration file is created with the defaults, but an existing configuration file that can not be parsed is an error.
+    #[default]
+    StrictAfterCreate,
+    /// A missing configuration file is created with the defaults. If an existing configuration file can not be parsed,
+    /// a warning is logged and the defaults are returned, without overwriting the configuration file.
+    Lenient,
+}
+
+/// Defines how [FileHandler::load_config] treats an existing configuration file that is empty or only contains whitespace,
+/// e.g. after a failed editor save or a truncated write, see [FileHandler::with_empty_file_policy].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyFilePolicy {
+    /// The configuration file is treated like a missing one: a warning is logged, and it is filled with the defaults.
+    #[default]
+    Defaults,
+    /// An empty configuration file is an error.
+    Error,
+}
+
+/// The state of the configuration directory or file, as reported by [FileHandler::diagnose].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStatus {
+    /// The path exists, has the expected type and is writable.
+    Writable,
+    /// The path does not exist. It is created when the configuration is loaded or saved.
+    Missing,
+    /// The path exists and has the expected type, but is read-only.
+    ReadOnly,
+    /// The path exists, but is a directory instead of a file or vice versa.
+    WrongType,
+    /// The metadata of the path can not be read, e.g. because a parent directory is not accessible.
+    Inaccessible(io::ErrorKind),
+}
+
+impl PathStatus {
+    /// Returns whether the status prevents loading or saving the configuration.
+    pub fn is_problem(&self) -> bool {
+        !matches!(self, PathStatus::Writable | PathStatus::Missing)
+    }
+}
+
+/// A report about the configuration directory and file of a [FileHandler], see [FileHandler::diagnose].
+///
+/// The `Display` implementation lists one actionable message per problem, or states that no problems were found.
+///
+/// # Fields
+///
+/// * `directory_path` - The path of the configuration directory.
+/// * `directory` - The state of the configuration directory.
+/// * `file_path` - The path of the configuration file.
+/// * `file` - The state of the configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub directory_path: PathBuf,
+    pub directory: PathStatus,
+    pub file_path: PathBuf,
+    pub file: PathStatus,
+}
+
+impl Diagnostics {
+    /// Returns whether neither the configuration directory nor the configuration file has a problem.
+    pub fn is_ok(&self) -> bool {
+        !self.directory.is_problem() && !self.file.is_problem()
+    }
+
+    /// Returns an actionable message for every problem, e.g. that the configuration directory is not writable.
+    pub fn messages(&self) -> Vec<String> {
+        let directory = self.directory_path.display();
+        let file = self.file_path.display();
+        let mut messages = Vec::new();
+
+        match self.directory {
+            PathStatus::Writable | PathStatus::Missing => {}
+            PathStatus::ReadOnly => messages.push(format!(
+                "Config directory {directory} exists but is not writable, make it writable for the current user"
+            )),
+            PathStatus::WrongType => messages.push(format!(
+                "Config directory {directory} exists but is not a directory, remove or rename it"
+            )),
+            PathStatus::Inaccessible(kind) => messages.push(format!(
+                "Config directory {directory} can not be accessed ({kind}), check the permissions of its parent directories"
+            )),
+        }
+
+        match self.file {
+            PathStatus::Writable => {}
+            PathStatus::Missing if self.directory == PathStatus::ReadOnly => messages.push(format!(
+                "Config file {file} does not exist and can not be created, as its directory is not writable"
+            )),
+            PathStatus::Missing => {}
+            PathStatus::ReadOnly => messages.push(format!(
+                "Config file {file} exists but is not writable, make it writable for the current user"
+            )),
+            PathStatus::WrongType => messages.push(format!(
+                "Config file {file} exists but is not a file, remove or rename it"
+            )),
+            PathStatus::Inaccessible(kind) => messages.push(format!(
+                "Config file {file} can not be accessed ({kind}), check the permissions of its directory"
+            )),
+        }
+
+        messages
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages = self.messages();
+        if messages.is_empty() {
+            return write!(formatter, "No problems found");
+        }
+
+        write!(formatter, "{}", messages.join("\n"))
+    }
+}
+
+/// The platform and host a [FileHandler] loads conditional overlays for, see [FileHandler::with_conditional_overlays].
+///
+/// # Fields
+///
+/// * `platform` - The name of the platform, e.g. `linux`, `macos` or `windows`.
+/// * `hostname` - The name of the host, if known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayTarget {
+    pub platform: String,
+    pub hostname: Option<String>,
+}
+
+impl OverlayTarget {
+    /// Returns the target of the running process.
+    ///
+    /// The platform is `std::env::consts::OS`. The hostname is read from the `HOSTNAME` or `COMPUTERNAME` environment variable,
+    /// or on Unix from `/etc/hostname` if neither is set.
+    pub fn current() -> Self {
+        let hostname = env::var("HOSTNAME")
+            .or_else(|_| env::var("COMPUTERNAME"))
+            .ok()
+            .or_else(|| {
+                #[cfg(unix)]
+                return fs::read_to_string("/etc/hostname").ok();
+                #[cfg(not(unix))]
+                return None;
+            })
+            .map(|hostname| hostname.trim().to_string())
+            .filter(|hostname| !hostname.is_empty());
+
+        OverlayTarget {
+            platform: env::consts::OS.to_string(),
+            hostname,
+        }
+    }
+}
+
+/// The maximum nesting depth of included files, see [FileHandler::with_includes].
+pub const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// The maximum size of a configuration file read by a [FileHandler], unless changed by [FileHandler::with_max_size].
+pub const DEFAULT_MAX_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Optional behavior of a [FileHandler], configured through its `with_*` methods.
+#[derive(Debug, Default)]
+struct FileHandlerOptions {
+    preserve_unknown: bool,
+    sorted_keys: bool,
+    readonly_tolerant: bool,
+    retry: RetryPolicy,
+    interpolation: bool,
+    bootstrap: Option<Bootstrap>,
+    on_create: Option<OnCreate>,
+    max_size: Option<u64>,
+    durable: bool,
+    strictness: Strictness,
+    empty_file_policy: EmptyFilePolicy,
+    overlay_target: Option<OverlayTarget>,
+    aliases: Vec<(String, String)>,
+    includes: Option<String>,
+    stdio: Option<Stdio>,
+    create_dirs_disabled: bool,
+    temp_dir: Option<PathBuf>,
+}
+
+/// The stream the configuration is read from and the one it is written to if its path is `-`, see [FileHandler::with_stdio].
+struct Stdio {
+    input: Mutex<Box<dyn Read + Send>>,
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+impl fmt::Debug for Stdio {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("Stdio")
+    }
+}
+
+/// A function producing the initial content of a missing configuration file, see [FileHandler::with_bootstrap].
+struct Bootstrap(Box<dyn Fn() -> String + Send + Sync>);
+
+impl fmt::Debug for Bootstrap {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("Bootstrap")
+    }
+}
+
+/// A function run after `load_config` created a missing configuration file, see [FileHandler::with_on_create].
+#[allow(clippy::type_complexity)]
+struct OnCreate(Box<dyn Fn(&Path) -> Result<(), Box<dyn StdError + Send + Sync>> + Send + Sync>);
+
+impl fmt::Debug for OnCreate {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("OnCreate")
+    }
+}
+
 impl<Config> FileHandler<Config>
 where
     Config: Serialize + for<'de> Deserialize<'de>,
@@ -87,102 +365,2498 @@ where
         config_directory: Option<IntoString>,
         config_file_name: Option<IntoString>,
     ) -> Result<Self, ConfigPathError> {
-        let app_name = app_name.into();
+        let config_directory = config_directory.map(Into::into);
+        let config_directory_path =
+            resolve_config_dir(&app_name.into(), config_directory.as_deref())?;
+
+        Ok(Self::from_directory(
+            config_directory_path,
+            config_file_name.map(Into::into),
+        ))
+    }
+
+    /// Creates a new `FileHandler`, preferring the `XDG_CONFIG_HOME` directory on all platforms.
+    ///
+    /// Many cross-platform tools honor `XDG_CONFIG_HOME` even on macOS and Windows, to keep a single configuration layout across machines.
+    /// The configuration directory is resolved in the following order:
+    /// 1. `XDG_CONFIG_HOME`, if it is set to an absolute path. As defined by the XDG Base Directory Specification, relative paths are ignored.
+    /// 2. The OS-specific configuration directory, like in [FileHandler::new].
+    ///
+    /// # Arguments
+    ///
+    /// * `app_name` - The name of the application. This is used to construct the configuration file path.
+    /// * `config_file_name` - An optional custom name for the configuration file. Defaults to `config.json`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the `FileHandler` instance.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigPathError`.
+    pub fn new_xdg<IntoString: Into<String>>(
+        app_name: IntoString,
+        config_file_name: Option<IntoString>,
+    ) -> Result<Self, ConfigPathError> {
+        let xdg_config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute());
+
+        let config_directory = match xdg_config_home.or_else(dirs::config_dir) {
+            Some(path) => path,
+            None => return Err(ConfigPathError::UnknownConfigDirectory),
+        };
+
+        Ok(Self::from_base_directory(
+            app_name.into(),
+            config_directory,
+            config_file_name.map(Into::into),
+        ))
+    }
 
-        let mut config_directory_path = match config_directory {
-            Some(config_directory) => PathBuf::from(config_directory.into()),
-            None => match dirs::config_dir() {
-                Some(path) => path,
-                None => return Err(ConfigPathError::UnknownConfigDirectory),
+    /// Creates a new `FileHandler` in the OS-specific configuration directory, falling back to `fallback` if it is unavailable.
+    ///
+    /// The OS-specific configuration directory can be unavailable in minimal environments, e.g. containers without `HOME`.
+    /// Instead of returning `ConfigPathError::UnknownConfigDirectory` like [FileHandler::new], `fallback` is used in that case.
+    /// A relative `fallback` (like `"."`) is resolved against the current working directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_name` - The name of the application. This is used to construct the configuration file path.
+    /// * `fallback` - The directory to use if the OS-specific configuration directory is unavailable.
+    /// * `config_file_name` - An optional custom name for the configuration file. Defaults to `config.json`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the `FileHandler` instance.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigPathError`,
+    ///   if the fallback is needed but relative, and the current working directory can not be determined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_config::FileHandler;
+    /// use std::path::PathBuf;
+    ///
+    /// let file_handler: FileHandler<()> =
+    ///     FileHandler::new_with_fallback("MyApp", PathBuf::from("."), None).unwrap();
+    ///
+    /// assert!(file_handler.config_directory_path.ends_with("MyApp"));
+    /// assert!(file_handler.config_directory_path.is_absolute());
+    /// ```
+    pub fn new_with_fallback<IntoString: Into<String>>(
+        app_name: IntoString,
+        fallback: PathBuf,
+        config_file_name: Option<IntoString>,
+    ) -> Result<Self, ConfigPathError> {
+        let config_directory = match dirs::config_dir() {
+            Some(path) => path,
+            None if fallback.is_absolute() => fallback,
+            None => match env::current_dir() {
+                Ok(current_directory) => current_directory.join(fallback),
+                Err(_) => return Err(ConfigPathError::UnknownConfigDirectory),
             },
         };
+
+        Ok(Self::from_base_directory(
+            app_name.into(),
+            config_directory,
+            config_file_name.map(Into::into),
+        ))
+    }
+
+    /// Creates a new `FileHandler` in the directory of the running executable, for portable installations.
+    ///
+    /// Unlike the other constructors, no application-specific subdirectory is used,
+    /// so the configuration file is placed right next to the executable, e.g. `config.json` beside `myapp.exe`.
+    /// The path of the executable is canonicalized, so if it is started through a symlink, the directory of the symlink's target is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_file_name` - An optional custom name for the configuration file. Defaults to `config.json`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the `FileHandler` instance.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigPathError::UnknownExecutableDirectory`,
+    ///   if the path of the executable can not be determined.
+    pub fn new_portable<IntoString: Into<String>>(
+        config_file_name: Option<IntoString>,
+    ) -> Result<Self, ConfigPathError> {
+        let executable_path = env::current_exe()
+            .and_then(fs::canonicalize)
+            .map_err(ConfigPathError::UnknownExecutableDirectory)?;
+        let config_directory_path = match executable_path.parent() {
+            Some(path) => path.to_path_buf(),
+            None => {
+                return Err(ConfigPathError::UnknownExecutableDirectory(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "executable has no parent directory",
+                )))
+            }
+        };
+
+        Ok(Self::from_directory(
+            config_directory_path,
+            config_file_name.map(Into::into),
+        ))
+    }
+
+    /// Creates a new `FileHandler` for the configuration file at exactly the given path.
+    ///
+    /// Unlike the other constructors, the path is used as is: no application-specific subdirectory is appended,
+    /// and the configuration directory is the parent directory of `path`.
+    /// A relative path is resolved against the current working directory whenever the file is accessed.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// A new `FileHandler` instance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use lum_config::FileHandler;
+    ///
+    /// let file_handler: FileHandler<()> = FileHandler::from_path("/etc/myapp/settings.json");
+    ///
+    /// assert_eq!(file_handler.config_file_path, Path::new("/etc/myapp/settings.json"));
+    /// assert_eq!(file_handler.config_directory_path, Path::new("/etc/myapp"));
+    /// ```
+    pub fn from_path<IntoPathBuf: Into<PathBuf>>(path: IntoPathBuf) -> Self {
+        let config_file_path = path.into();
+        let config_directory_path = config_file_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        FileHandler {
+            config_file_path,
+            ..Self::from_directory(config_directory_path, None)
+        }
+    }
+
+    fn from_base_directory(
+        app_name: String,
+        mut config_directory_path: PathBuf,
+        config_file_name: Option<String>,
+    ) -> Self {
         config_directory_path.push(app_name);
 
-        let config_file_name = config_file_name
-            .map(Into::into)
-            .unwrap_or("config.json".into());
+        Self::from_directory(config_directory_path, config_file_name)
+    }
+
+    fn from_directory(config_directory_path: PathBuf, config_file_name: Option<String>) -> Self {
+        let config_file_name = config_file_name.unwrap_or("config.json".into());
         let config_file_path = config_directory_path.join(config_file_name);
 
-        Ok(FileHandler {
+        FileHandler {
             config_directory_path,
             config_file_path,
+            codec: JsonCodec::default(),
+            file_system: StdFs,
+            options: FileHandlerOptions::default(),
+            unknown_keys: Mutex::new(Map::new()),
             _phantom_file: PhantomData,
-        })
+        }
     }
+}
 
-    /// Creates the configuration directory if it does not exist.
+impl<Config, FileStorage> FileHandler<Config, JsonCodec, FileStorage>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    FileStorage: FileSystem,
+{
+    /// Sets the [JsonStyle] used to format the configuration file when saving.
     ///
-    /// **This does not need to be called manually** as it is called by `load_config` and `save_config`.
-    /// However, it can be called manually, if you need it.
+    /// Defaults to pretty-printed JSON, indented with two spaces.
+    ///
+    /// # Arguments
+    ///
+    /// * `style` - The formatting to use.
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or failure.
-    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
-    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
-    pub fn create_config_directory(&self) -> Result<(), io::Error> {
-        let path = &self.config_directory_path;
-        fs::create_dir_all(path)?;
-
-        Ok(())
+    /// The `FileHandler` instance with the style applied.
+    pub fn with_json_style(mut self, style: JsonStyle) -> Self {
+        self.codec.style = style;
+        self
     }
+}
 
-    /// Saves the configuration to the configuration file.
+impl<Config, FileCodec, FileStorage> FileHandler<Config, FileCodec, FileStorage>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    FileCodec: Codec,
+    FileStorage: FileSystem,
+{
+    /// Replaces the [Codec] used to read and write the configuration file.
     ///
-    /// If the configuration directory does not exist, it will be created.
+    /// This allows using file formats that are not bundled with this crate.
+    /// The paths of the `FileHandler` are kept as they are, so you might want to pass a matching `config_file_name` to [FileHandler::new].
     ///
-    /// If the configuration file does not exist, it will be created.
+    /// The format is never detected from the file extension: the codec is always authoritative.
+    /// A `config.conf` file, or a file without extension, is read and written as JSON by the default [JsonCodec].
     ///
-    /// If the configuration file already exists, it will be overwritten.
+    /// # Arguments
+    ///
+    /// * `codec` - The codec to use from now on.
+    ///
+    /// # Returns
+    ///
+    /// A new `FileHandler` instance using the given codec.
+    pub fn with_codec<NewCodec: Codec>(
+        self,
+        codec: NewCodec,
+    ) -> FileHandler<Config, NewCodec, FileStorage> {
+        FileHandler {
+            config_directory_path: self.config_directory_path,
+            config_file_path: self.config_file_path,
+            codec,
+            file_system: self.file_system,
+            options: self.options,
+            unknown_keys: self.unknown_keys,
+            _phantom_file: PhantomData,
+        }
+    }
+
+    /// Returns the [Codec] used to read and write the configuration file.
+    pub fn codec(&self) -> &FileCodec {
+        &self.codec
+    }
+
+    /// Replaces the [FileSystem] used to access the configuration file, e.g. with an in-memory filesystem in tests.
+    ///
+    /// All operations on the configuration directory and file go through the filesystem, including the files of profiles,
+    /// `conf.d` fragments and includes. Only [crate::file_handler::load_ndjson] and the constructors, which do not belong to a `FileHandler`,
+    /// as well as the cycle detection of [FileHandler::with_includes], which canonicalizes paths, use `std::fs` directly.
     ///
     /// # Arguments
     ///
-    /// * `config` - The configuration to be saved.
+    /// * `file_system` - The filesystem to use from now on.
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or failure.
-    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
-    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
-    pub fn save_config(&self, config: &Config) -> Result<(), ConfigSaveError> {
-        self.create_config_directory()?;
+    /// A new `FileHandler` instance using the given filesystem.
+    pub fn with_file_system<NewFileSystem: FileSystem>(
+        self,
+        file_system: NewFileSystem,
+    ) -> FileHandler<Config, FileCodec, NewFileSystem> {
+        FileHandler {
+            config_directory_path: self.config_directory_path,
+            config_file_path: self.config_file_path,
+            codec: self.codec,
+            file_system,
+            options: self.options,
+            unknown_keys: self.unknown_keys,
+            _phantom_file: PhantomData,
+        }
+    }
 
-        let config_json = serde_json::to_string_pretty(config)?;
-        fs::write(&self.config_file_path, config_json)?;
+    /// Returns the [FileSystem] used to access the configuration file.
+    pub fn file_system(&self) -> &FileStorage {
+        &self.file_system
+    }
 
-        Ok(())
+    /// Enables or disables preserving unknown keys of the configuration file.
+    ///
+    /// By default, keys of the configuration file that do not belong to `Config` are dropped on load,
+    /// and therefore removed from the file when it is saved again.
+    /// This is a problem when multiple versions of an application share the same configuration file,
+    /// as an older version would delete the settings of a newer one.
+    ///
+    /// When enabled, `load_config` captures all keys that are not part of `Config` (including nested ones),
+    /// and `save_config` writes them back alongside the known keys.
+    /// Keys written by `save_config` always take precedence over captured unknown keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `preserve_unknown` - Whether unknown keys should be preserved.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_preserve_unknown(mut self, preserve_unknown: bool) -> Self {
+        self.options.preserve_unknown = preserve_unknown;
+        self
     }
 
-    /// Loads the configuration from the configuration file.
+    /// Enables or disables sorting the keys of maps when saving the configuration file.
     ///
-    /// If the configuration directory does not exist, it will be created.
+    /// Maps like `HashMap` are serialized in an unspecified order, which changes every time the file is rewritten
+    /// and results in noisy diffs. When enabled, the keys of all maps and structs, including nested ones,
+    /// are written in lexicographical order, making the file diff-stable.
     ///
-    /// If the configuration file does not exist, it will be created with an empty JSON object.
+    /// This only affects the order in the written file, not the in-memory `Config` type.
     ///
-    /// **To be able to create a fresh config file, or insert missing attributes,
-    /// make sure that your configuration type has a default implementation
-    /// (either by deriving `Default` or implementing the Default trait),
-    /// and that \#[serde(default)] is used on the struct.**
-    /// Otherwise, you will get a `serde` error at runtime, complaining about missing fields.
-    /// For an example, see the example in the documentation of the [FileHandler#examples] struct.
+    /// # Arguments
+    ///
+    /// * `sorted_keys` - Whether keys should be sorted.
     ///
     /// # Returns
     ///
-    /// A `Result` indicating success or failure.
-    /// * Success is indicated by an `Ok` value, containing the Config instance.
-    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
-    pub fn load_config(&self) -> Result<Config, FileConfigParseError> {
-        self.create_config_directory()?;
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_sorted_keys(mut self, sorted_keys: bool) -> Self {
+        self.options.sorted_keys = sorted_keys;
+        self
+    }
 
-        let path = &self.config_file_path;
-        if !path.exists() {
-            fs::write(path, "{}")?;
-        }
+    /// Enables or disables tolerating a read-only configuration file or directory in `load_config`.
+    ///
+    /// After reading the configuration file, `load_config` saves it again to backfill missing fields.
+    /// On read-only filesystems (e.g. read-only container mounts like `/etc`), this fails and makes the whole load fail,
+    /// even though the configuration was read successfully.
+    ///
+    /// When enabled, a failed backfill save due to a read-only filesystem or missing permissions is logged as a warning,
+    /// and the parsed configuration is returned anyway. All other errors are still returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `readonly_tolerant` - Whether a read-only target should be tolerated.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_readonly_tolerant(mut self, readonly_tolerant: bool) -> Self {
+        self.options.readonly_tolerant = readonly_tolerant;
+        self
+    }
 
-        let config_json = fs::read_to_string(path)?;
-        let config = serde_json::from_str(&config_json)?;
-        self.save_config(&config)?; // In case the config file was missing some fields which serde used the defaults for
+    /// Enables or disables the interpolation of environment variables in string values of the configuration file in `load_config`.
+    ///
+    /// When enabled, references to environment variables in string values (not in keys) are substituted before deserializing `Config`:
+    /// * `${VAR}` and `$VAR` are replaced by the value of `VAR`. Variable names consist of ASCII letters, digits and `_`, and must not start with a digit.
+    /// * `${VAR:-default}` is replaced by the value of `VAR`, or by `default` if `VAR` is not set.
+    /// * `$$` is replaced by a single `$`. Any other `$` is kept as it is.
+    ///
+    /// If a referenced variable is not set and has no default, `FileConfigParseError::UnresolvedInterpolation` is returned.
+    ///
+    /// Interpolated values are never written back by `load_config`: when backfilling missing fields, the references are kept as they are.
+    /// Note that `save_config` writes the given configuration as it is, so saving a loaded configuration replaces the references by their values.
+    ///
+    /// # Arguments
+    ///
+    /// * `interpolation` - Whether environment variables should be interpolated.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_interpolation(mut self, interpolation: bool) -> Self {
+        self.options.interpolation = interpolation;
+        self
+    }
 
-        Ok(config)
+    /// Sets aliases for renamed top-level keys of the configuration file, honoring the old keys during a grace period.
+    ///
+    /// When loading, every deprecated key found in the configuration file is renamed to its new key before deserializing `Config`,
+    /// and a warning is logged for it. If both keys are present, the value of the new key is used and the deprecated key is dropped.
+    /// As `load_config` saves the configuration again afterwards, the configuration file is migrated to the new keys.
+    ///
+    /// # Arguments
+    ///
+    /// * `aliases` - Pairs of the deprecated key and the key replacing it.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the aliases applied.
+    pub fn with_aliases(mut self, aliases: &[(&str, &str)]) -> Self {
+        self.options.aliases = aliases
+            .iter()
+            .map(|(old_key, new_key)| (old_key.to_string(), new_key.to_string()))
+            .collect();
+        self
     }
+
+    /// Sets how `load_config` treats an existing configuration file that is empty or only contains whitespace.
+    ///
+    /// Parsing such a file would fail with an error like "EOF while parsing a value", which is hard to understand for users.
+    /// Defaults to [EmptyFilePolicy::Defaults], which fills the file with the same content a missing file is created with (see [FileHandler::with_bootstrap]).
+    /// With [EmptyFilePolicy::Error], `FileConfigParseError::EmptyFile` is returned instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The policy to apply.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the policy applied.
+    pub fn with_empty_file_policy(mut self, policy: EmptyFilePolicy) -> Self {
+        self.options.empty_file_policy = policy;
+        self
+    }
+
+    /// Enables including other files, listed under the given top-level key of the configuration file.
+    ///
+    /// When loading, the key (e.g. `"include": ["base.json", "overrides.json"]`) is removed from the configuration file,
+    /// and the listed files are loaded and merged in order, beneath the configuration file:
+    /// later files override earlier ones, and the configuration file overrides all of them.
+    /// Relative paths are resolved against the directory of the including file. Included files may include further files.
+    ///
+    /// Including a file that is already being included (a cycle), or nesting includes deeper than [MAX_INCLUDE_DEPTH],
+    /// results in `FileConfigParseError::IncludeCycle`. An included file that can not be read or decoded (e.g. because it is missing)
+    /// results in `FileConfigParseError::Include`, naming the included file.
+    ///
+    /// As the included values must not be copied into the configuration file, `load_config` does not save the configuration again after loading it.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The top-level key listing the files to include.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_includes(mut self, key: &str) -> Self {
+        self.options.includes = Some(key.to_string());
+        self
+    }
+
+    /// Sets the function producing the content that is written when `load_config` finds no configuration file.
+    ///
+    /// By default, an empty document is written (an empty JSON object for the default [JsonCodec]),
+    /// which relies on \#[serde(default)] to fill in all fields.
+    /// Use this to bootstrap configuration types without full default coverage, codecs for which an empty object is not valid,
+    /// or to write a template with comments and examples.
+    ///
+    /// The content is written as it is and parsed like any other configuration file afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `bootstrap` - The function producing the initial content of the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the bootstrap function set.
+    pub fn with_bootstrap<Bootstrapper>(mut self, bootstrap: Bootstrapper) -> Self
+    where
+        Bootstrapper: Fn() -> String + Send + Sync + 'static,
+    {
+        self.options.bootstrap = Some(Bootstrap(Box::new(bootstrap)));
+        self
+    }
+
+    /// Sets a function that is run once after `load_config` created a missing configuration file, e.g. to restrict its permissions or to print a notice.
+    ///
+    /// The function is called with the path of the configuration file, after the file was written completely,
+    /// including the defaults backfilled by `load_config`. It is not called when the file already existed, or when it could not be created.
+    ///
+    /// If the function returns an error, `load_config` returns `FileConfigParseError::OnCreate` containing it.
+    /// The created file is kept in that case, so the function is not called again by the next `load_config`.
+    ///
+    /// # Arguments
+    ///
+    /// * `on_create` - The function to run after creating the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the function set.
+    pub fn with_on_create<Hook>(mut self, on_create: Hook) -> Self
+    where
+        Hook: Fn(&Path) -> Result<(), Box<dyn StdError + Send + Sync>> + Send + Sync + 'static,
+    {
+        self.options.on_create = Some(OnCreate(Box::new(on_create)));
+        self
+    }
+
+    /// Sets the maximum size of the configuration file (and of the fragments of [FileHandler::load_directory]) in bytes.
+    ///
+    /// Files are read through a bounded reader, so a huge file or an endless stream (e.g. a FIFO) never allocates more than the limit.
+    /// Reading a larger file results in `FileConfigParseError::TooLarge`. Defaults to [DEFAULT_MAX_SIZE] (8 MiB).
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - The maximum size in bytes.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the limit applied.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.options.max_size = Some(max_size);
+        self
+    }
+
+    /// Sets the directory the temporary file of durable writes (see [FileHandler::with_durable]) is created in.
+    ///
+    /// By default, the temporary file is created next to the configuration file. This fails if the configuration directory is read-only
+    /// while the configuration file itself is writable, and a separate temporary directory may also be on a faster disk.
+    ///
+    /// Renaming a file is only atomic within a filesystem, so the directory is only used if it is on the same filesystem as the configuration directory.
+    /// This is detected by comparing the device IDs, which are only available on Unix. If the devices differ or can not be compared,
+    /// e.g. on other platforms or if the directory does not exist, a warning is logged and the temporary file is created next to the configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `temp_dir` - The directory to create the temporary file in.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the temporary directory set.
+    pub fn with_temp_dir<IntoPathBuf: Into<PathBuf>>(mut self, temp_dir: IntoPathBuf) -> Self {
+        self.options.temp_dir = Some(temp_dir.into());
+        self
+    }
+
+    /// Replaces the streams used instead of stdin and stdout if the configuration file path is `-`, e.g. to simulate stdin in tests.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The stream the configuration is read from.
+    /// * `output` - The stream the configuration is written to.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the streams set.
+    pub fn with_stdio<Input, Output>(mut self, input: Input, output: Output) -> Self
+    where
+        Input: Read + Send + 'static,
+        Output: Write + Send + 'static,
+    {
+        self.options.stdio = Some(Stdio {
+            input: Mutex::new(Box::new(input)),
+            output: Mutex::new(Box::new(output)),
+        });
+        self
+    }
+
+    /// Returns whether the configuration is read from stdin and written to stdout, which is the case if the name of the configuration file is `-`.
+    ///
+    /// Following the convention of command line tools, e.g. `cat config.json | myapp --config -`,
+    /// [FileHandler::load_config] then reads the document from stdin, and saving writes it to stdout.
+    /// As stdin can not be written back, the configuration directory is never created, and the loaded configuration is not saved again.
+    /// Note that stdin can only be read once: loading again reads an empty document.
+    /// See [FileHandler::with_stdio] to use other streams.
+    pub fn is_stdio(&self) -> bool {
+        self.config_file_path.file_name() == Some("-".as_ref())
+    }
+
+    /// Enables or disables creating the configuration directory in `load_config`, `save_config` and the other methods writing the configuration file.
+    ///
+    /// In sandboxed or immutable environments, creating directories may be forbidden, so it has to be managed externally.
+    /// When disabled, these methods never create a directory. If the configuration directory is missing,
+    /// they fail with an `io::Error` of kind `io::ErrorKind::NotFound` naming the directory instead.
+    /// Calling [FileHandler::create_config_directory] explicitly still creates it. Enabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `create_dirs` - Whether the configuration directory should be created if it is missing.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_create_dirs(mut self, create_dirs: bool) -> Self {
+        self.options.create_dirs_disabled = !create_dirs;
+        self
+    }
+
+    /// Enables or disables durable writes of the configuration file in `save_config` and `patch`.
+    ///
+    /// When enabled, the configuration is written to a temporary file next to the configuration file (`.<file name>.tmp`, see [FileHandler::with_temp_dir]),
+    /// which is flushed to disk with `File::sync_all` and then renamed over the configuration file.
+    /// This way, the configuration file always contains either the old or the new configuration, even after a crash or power loss.
+    /// On Unix, the configuration directory is synced after the rename as well, so the rename itself is durable.
+    ///
+    /// Syncing is slow, so this is disabled by default. Platform caveats:
+    /// * On Windows, directories can not be synced, so the rename may be lost on a power loss right after saving.
+    /// * Some filesystems (e.g. some network filesystems) do not support syncing directories. A failure to do so is logged as a warning.
+    /// * On Windows, the rename fails if another process holds the configuration file open. Consider [FileHandler::with_retry].
+    ///
+    /// # Arguments
+    ///
+    /// * `durable` - Whether the configuration file should be written durably.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the option applied.
+    pub fn with_durable(mut self, durable: bool) -> Self {
+        self.options.durable = durable;
+        self
+    }
+
+    /// Sets how strictly `load_config` treats a missing or invalid configuration file.
+    ///
+    /// Defaults to [Strictness::StrictAfterCreate]: a missing configuration file is bootstrapped,
+    /// but once it exists, a configuration file that can not be parsed is a hard error,
+    /// so a user's broken edit is never silently discarded.
+    /// See [Strictness] for the other modes, and [FileHandler::load_config_recover] to replace corrupt configuration files instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `strictness` - The strictness to apply.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the strictness applied.
+    pub fn with_strictness(mut self, strictness: Strictness) -> Self {
+        self.options.strictness = strictness;
+        self
+    }
+
+    /// Enables merging platform- and host-specific overlay files onto the configuration in `load_config`.
+    ///
+    /// Overlay files are named like profile files (see [FileHandler::list_profiles]), with the platform or hostname as the profile name,
+    /// e.g. `config.linux.json` and `config.<hostname>.json` for the default `config.json`.
+    /// They are merged onto the loaded configuration in the following order, where later files override earlier ones:
+    /// 1. The configuration file.
+    /// 2. The platform overlay.
+    /// 3. The hostname overlay.
+    ///
+    /// Objects are merged recursively, every other value of an overlay replaces the previous value.
+    /// Missing overlay files are skipped, but an overlay file that can not be parsed is an error.
+    /// Overlays are only applied to the returned configuration, and never written to the configuration file.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - The platform and host to load overlays for, usually [OverlayTarget::current].
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with conditional overlays enabled.
+    pub fn with_conditional_overlays(mut self, target: OverlayTarget) -> Self {
+        self.options.overlay_target = Some(target);
+        self
+    }
+
+    /// Sets the [RetryPolicy] for writing the configuration file in `save_config` and `patch`.
+    ///
+    /// On network filesystems (e.g. NFS or SMB) or while an antivirus scanner holds the file open, writes may fail intermittently.
+    /// Writes that fail with a transient error (see [RetryPolicy::is_transient]) are retried up to `attempts` times in total,
+    /// with exponential backoff starting at `backoff`. Permanent errors, e.g. missing permissions, are returned immediately.
+    /// If the last attempt fails, `ConfigSaveError::IO` is returned.
+    ///
+    /// By default, writes are not retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `attempts` - The maximum number of attempts, including the first one.
+    /// * `backoff` - The time to wait before the first retry.
+    ///
+    /// # Returns
+    ///
+    /// The `FileHandler` instance with the retry policy applied.
+    pub fn with_retry(mut self, attempts: u32, backoff: Duration) -> Self {
+        self.options.retry = RetryPolicy::new(attempts, backoff);
+        self
+    }
+
+    /// Lists the profiles that have a configuration file in the configuration directory.
+    ///
+    /// A profile file is named like the configuration file, with the profile name inserted before the extension.
+    /// For the default `config.json`, the files `config.dev.json` and `config.staging.json` belong to the profiles `dev` and `staging`.
+    /// If the configuration file has no extension, the profile name is appended instead, e.g. `settings.dev`.
+    ///
+    /// The configuration file itself does not belong to a profile and is not listed.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the sorted profile names. A missing configuration directory results in an empty `Vec`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    pub fn list_profiles(&self) -> Result<Vec<String>, io::Error> {
+        let entries = match self.file_system.read_dir(&self.config_directory_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+
+        let stem = self
+            .config_file_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let prefix = format!("{}.", stem);
+        let suffix = match self
+            .config_file_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some(extension) => format!(".{}", extension),
+            None => String::new(),
+        };
+
+        let mut profiles = Vec::new();
+        for entry in entries {
+            if !self.file_system.metadata(&entry)?.is_file {
+                continue;
+            }
+
+            let profile = entry
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .and_then(|file_name| file_name.strip_prefix(&prefix))
+                .and_then(|rest| rest.strip_suffix(&suffix));
+
+            if let Some(profile) = profile.filter(|profile| !profile.is_empty()) {
+                profiles.push(profile.to_string());
+            }
+        }
+        profiles.sort();
+
+        Ok(profiles)
+    }
+
+    /// Returns the last modification time of the configuration file.
+    ///
+    /// This only reads metadata and never creates the configuration file or directory.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the modification time, or `None` if the file does not exist.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    pub fn modified(&self) -> Result<Option<SystemTime>, io::Error> {
+        match self.metadata()? {
+            Some(metadata) => Ok(Some(metadata.modified)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the size of the configuration file in bytes.
+    ///
+    /// This only reads metadata and never creates the configuration file or directory.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the size, or `None` if the file does not exist.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    pub fn size(&self) -> Result<Option<u64>, io::Error> {
+        Ok(self.metadata()?.map(|metadata| metadata.len))
+    }
+
+    /// Checks the configuration directory and file for problems that would make loading or saving fail, e.g. missing write permissions.
+    ///
+    /// Unlike a failed `save_config`, whose `io::Error` does not tell whether the directory or the file is the culprit,
+    /// the returned [Diagnostics] reports the state of both, with an actionable message for every problem.
+    ///
+    /// This only reads metadata and never creates, opens or modifies anything.
+    /// Writability is derived from the permissions (see `std::fs::Permissions::readonly`), so on Unix a path
+    /// that is writable by its owner only is reported as writable, even if it is owned by another user.
+    ///
+    /// # Returns
+    ///
+    /// The [Diagnostics] of the configuration directory and file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lum_libs::serde::{Deserialize, Serialize};
+    /// use lum_config::{file_handler::PathStatus, FileHandler};
+    ///
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct Config {
+    ///     key: String,
+    /// }
+    ///
+    /// let file_handler: FileHandler<Config> = FileHandler::from_path("/nonexistent/lum/config.json");
+    /// let diagnostics = file_handler.diagnose();
+    ///
+    /// assert_eq!(diagnostics.file, PathStatus::Missing);
+    /// assert!(diagnostics.is_ok());
+    /// ```
+    pub fn diagnose(&self) -> Diagnostics {
+        let status = |path: &Path, expect_file: bool| match self.file_system.metadata(path) {
+            Ok(metadata) if metadata.is_file != expect_file => PathStatus::WrongType,
+            Ok(metadata) if metadata.readonly => PathStatus::ReadOnly,
+            Ok(_) => PathStatus::Writable,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => PathStatus::Missing,
+            Err(error) => PathStatus::Inaccessible(error.kind()),
+        };
+
+        Diagnostics {
+            directory_path: self.config_directory_path.clone(),
+            directory: status(&self.config_directory_path, false),
+            file_path: self.config_file_path.clone(),
+            file: status(&self.config_file_path, true),
+        }
+    }
+
+    fn metadata(&self) -> Result<Option<FileMetadata>, io::Error> {
+        match self.file_system.metadata(&self.config_file_path) {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Creates the configuration directory if it does not exist.
+    ///
+    /// **This does not need to be called manually** as it is called by `load_config` and `save_config`.
+    /// However, it can be called manually, if you need it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    pub fn create_config_directory(&self) -> Result<(), io::Error> {
+        let path = &self.config_directory_path;
+        self.file_system.create_dir_all(path)?;
+
+        Ok(())
+    }
+
+    /// Creates the configuration directory, or checks that it exists if [FileHandler::with_create_dirs] is disabled.
+    fn prepare_config_directory(&self) -> Result<(), io::Error> {
+        if !self.options.create_dirs_disabled {
+            return self.create_config_directory();
+        }
+
+        let path = &self.config_directory_path;
+        if path.as_os_str().is_empty() || self.file_system.exists(path) {
+            return Ok(());
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "Config directory {} does not exist, and creating it is disabled",
+                path.display()
+            ),
+        ))
+    }
+
+    /// Saves the configuration to the configuration file.
+    ///
+    /// If the configuration directory does not exist, it will be created, unless disabled by [FileHandler::with_create_dirs].
+    ///
+    /// If the configuration file does not exist, it will be created.
+    ///
+    /// If the configuration file already exists, it will be overwritten.
+    ///
+    /// If something other than a file (e.g. a directory) exists at the configuration file path, `ConfigSaveError::NotAFile` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to be saved.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn save_config(&self, config: &Config) -> Result<(), ConfigSaveError> {
+        let _config_string = self.write_config(config)?;
+        debug_event!(
+            "Saved config file {} ({} bytes)",
+            self.config_file_path.display(),
+            _config_string.len()
+        );
+
+        Ok(())
+    }
+
+    /// Saves the configuration to the configuration file, but only if it does not exist yet.
+    ///
+    /// The file is created with [FileSystem::write_new], so checking for its existence and creating it is a single atomic operation.
+    /// Unlike calling [ConfigStore::exists] before [FileHandler::save_config], this never overwrites a file created concurrently,
+    /// e.g. by `load_config` in another process.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to be saved.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing `true` if the file was created, or `false` if it already existed.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn save_config_if_absent(&self, config: &Config) -> Result<bool, ConfigSaveError> {
+        self.prepare_config_directory()?;
+        if self.config_path_is_not_a_file() {
+            return Err(ConfigSaveError::NotAFile(self.config_file_path.clone()));
+        }
+
+        let config_string = self.serialize_config(config)?;
+        match self
+            .file_system
+            .write_new(&self.config_file_path, config_string.as_bytes())
+        {
+            Ok(()) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Saves the configuration to the configuration file, like [FileHandler::save_config],
+    /// and returns the content hash of the written file, as [FileHandler::content_hash] would.
+    ///
+    /// This allows recognizing your own writes when watching the configuration file for external modifications.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to be saved.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the content hash of the written file.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn save_config_hashed(&self, config: &Config) -> Result<u64, ConfigSaveError> {
+        let config_string = self.write_config(config)?;
+        let hash = self
+            .hash_document(&config_string)
+            .map_err(|error| ConfigSaveError::Codec(Box::new(error)))?;
+
+        Ok(hash)
+    }
+
+    /// Updates only the keys of the configuration file that are present in `patch`.
+    ///
+    /// The current file is read as a generic document, `patch` is serialized and deep-merged into it (see [crate::merger::merge_values]),
+    /// and the result is written back. All keys that are not part of `patch` are left intact,
+    /// including unknown keys that do not belong to `Config`. This is safer than overwriting the whole file,
+    /// e.g. when an in-memory configuration contains runtime-only overrides, or when other tools edit the same file.
+    ///
+    /// If the configuration file does not exist, it is created containing only `patch`.
+    ///
+    /// # Arguments
+    ///
+    /// * `patch` - The values to be written, e.g. a struct containing a subset of the fields of `Config`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn patch<Patch: Serialize>(&self, patch: &Patch) -> Result<(), ConfigSaveError> {
+        self.prepare_config_directory()?;
+        if self.config_path_is_not_a_file() {
+            return Err(ConfigSaveError::NotAFile(self.config_file_path.clone()));
+        }
+
+        let mut document = match self.read_config_file_if_exists()? {
+            Some(config_string) => self
+                .codec
+                .from_str(&config_string)
+                .map_err(|error| ConfigSaveError::Codec(Box::new(error)))?,
+            None => Value::Object(Map::new()),
+        };
+        merger::merge_values(&mut document, serde_json::to_value(patch)?);
+        if self.options.sorted_keys {
+            sort_keys(&mut document);
+        }
+
+        let config_string = self
+            .codec
+            .to_string(&document)
+            .map_err(|error| ConfigSaveError::Codec(Box::new(error)))?;
+        self.write_file(&config_string)?;
+
+        Ok(())
+    }
+
+    /// Overwrites the configuration file with the defaults of `Config`, e.g. for a "restore defaults" button.
+    ///
+    /// Keys preserved by [FileHandler::with_preserve_unknown] are discarded as well.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the default Config instance that was written.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn reset_to_default(&self) -> Result<Config, ConfigSaveError>
+    where
+        Config: Default,
+    {
+        let config = Config::default();
+        self.lock_unknown_keys().clear();
+        self.save_config(&config)?;
+
+        Ok(config)
+    }
+
+    /// Formats the configuration exactly like `save_config` would write it, without touching the configuration file.
+    ///
+    /// This honors the codec and the options of this `FileHandler`, e.g. [FileHandler::with_json_style] and [FileHandler::with_sorted_keys].
+    /// Use it to display the effective configuration, e.g. for a `config show` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to be formatted.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the formatted `String`.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn format_config(&self, config: &Config) -> Result<String, ConfigSaveError> {
+        self.serialize_config(config)
+    }
+
+    /// Computes a hash of the content of the configuration file.
+    ///
+    /// The hash is computed from the parsed document rather than the raw bytes,
+    /// so formatting differences like indentation, whitespace, or key order do not change it.
+    /// It is stable across runs, platforms, and Rust versions, which makes it suitable for telling apart
+    /// your own writes from external modifications of the file.
+    ///
+    /// This only reads the configuration file and never creates it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the hash, or `None` if the file does not exist.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`. A file that can not be parsed,
+    ///   or that exceeds [FileHandler::with_max_size], results in an `io::ErrorKind::InvalidData` error.
+    pub fn content_hash(&self) -> Result<Option<u64>, io::Error> {
+        let Some(config_string) = self.read_config_file_if_exists()? else {
+            return Ok(None);
+        };
+
+        let hash = self
+            .hash_document(&config_string)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        Ok(Some(hash))
+    }
+
+    /// Returns whether the configuration file contains the given configuration, e.g. to warn about unsaved changes.
+    ///
+    /// The configuration file is parsed like in [FileHandler::load_config], including includes, interpolation and overlays,
+    /// and both configurations are compared as parsed values. Formatting differences like indentation, whitespace, or key order,
+    /// as well as fields missing from the file that are filled in with their defaults, do not count as differences.
+    ///
+    /// This only reads the configuration file and never creates or modifies it.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to compare with the configuration file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing `true` if the configuration file contains `config`,
+    ///   or `false` if it differs, is empty, or does not exist.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError` if the configuration file can not be read or parsed.
+    pub fn is_in_sync(&self, config: &Config) -> Result<bool, FileConfigParseError> {
+        if self.is_stdio() {
+            return Err(FileConfigParseError::IO(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Config from stdin can not be compared, as it can not be read again",
+            )));
+        }
+
+        let config_string = match self.read_bounded(&self.config_file_path) {
+            Ok(config_string) => config_string,
+            Err(FileConfigParseError::IO(error)) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(false)
+            }
+            Err(error) => return Err(error),
+        };
+        if config_string.trim().is_empty() {
+            return Ok(false);
+        }
+
+        // Parsing replaces the unknown keys, which must not change without loading the configuration
+        let unknown_keys = self.lock_unknown_keys().clone();
+        let parsed = self.parse_document(&config_string);
+        *self.lock_unknown_keys() = unknown_keys;
+        let saved = self.apply_overlays(parsed?.0)?;
+
+        Ok(serde_json::to_value(&saved)? == serde_json::to_value(config)?)
+    }
+
+    /// Serializes the configuration, honoring all formatting options.
+    fn serialize_config(&self, config: &Config) -> Result<String, ConfigSaveError> {
+        let config_string = if self.options.preserve_unknown || self.options.sorted_keys {
+            let mut value = serde_json::to_value(config)?;
+            if self.options.preserve_unknown {
+                let unknown_keys = self.lock_unknown_keys();
+                insert_unknown_keys(&mut value, &unknown_keys);
+            }
+            if self.options.sorted_keys {
+                sort_keys(&mut value);
+            }
+
+            self.codec.to_string(&value)
+        } else {
+            self.codec.to_string(config)
+        }
+        .map_err(|error| ConfigSaveError::Codec(Box::new(error)))?;
+
+        Ok(config_string)
+    }
+
+    /// Writes the configuration to the configuration file, returning the written content.
+    fn write_config(&self, config: &Config) -> Result<String, ConfigSaveError> {
+        if self.is_stdio() {
+            let config_string = self.serialize_config(config)?;
+            match &self.options.stdio {
+                Some(stdio) => {
+                    let mut output = lock(&stdio.output);
+                    output.write_all(config_string.as_bytes())?;
+                    output.flush()?;
+                }
+                None => {
+                    let mut output = io::stdout().lock();
+                    output.write_all(config_string.as_bytes())?;
+                    output.flush()?;
+                }
+            }
+
+            return Ok(config_string);
+        }
+
+        self.prepare_config_directory()?;
+        if self.config_path_is_not_a_file() {
+            return Err(ConfigSaveError::NotAFile(self.config_file_path.clone()));
+        }
+
+        let config_string = self.serialize_config(config)?;
+        self.write_file(&config_string)?;
+
+        Ok(config_string)
+    }
+
+    /// Hashes the normalized form of a document: parsed, with sorted keys, and serialized as compact JSON.
+    fn hash_document(&self, document: &str) -> Result<u64, FileCodec::Error> {
+        let mut value: Value = self.codec.from_str(document)?;
+        sort_keys(&mut value);
+
+        Ok(fnv1a_hash(value.to_string().as_bytes()))
+    }
+
+    /// Loads the configuration from the configuration file.
+    ///
+    /// If the configuration directory does not exist, it will be created, unless disabled by [FileHandler::with_create_dirs].
+    ///
+    /// If the configuration file does not exist, it will be created with an empty document (an empty JSON object for the default [JsonCodec]).
+    ///
+    /// If something other than a file (e.g. a directory) exists at the configuration file path, `FileConfigParseError::NotAFile` is returned.
+    ///
+    /// If the name of the configuration file is `-`, the configuration is read from stdin instead, see [FileHandler::is_stdio].
+    ///
+    /// **To be able to create a fresh config file, or insert missing attributes,
+    /// make sure that your configuration type has a default implementation
+    /// (either by deriving `Default` or implementing the Default trait),
+    /// and that \#[serde(default)] is used on the struct.**
+    /// Otherwise, you will get a `serde` error at runtime, complaining about missing fields.
+    /// For an example, see the example in the documentation of the [FileHandler#examples] struct.
+    ///
+    /// If the configuration file is read-only, see [FileHandler::with_readonly_tolerant].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn load_config(&self) -> Result<Config, FileConfigParseError> {
+        debug_event!("Loading config file {}", self.config_file_path.display());
+        #[cfg(feature = "log")]
+        let started = std::time::Instant::now();
+
+        if self.is_stdio() {
+            return self.load_stdin();
+        }
+
+        let path = &self.config_file_path;
+        // Check before preparing the directory, as a strict load must not create anything
+        if self.options.strictness == Strictness::Strict && !self.file_system.exists(path) {
+            return Err(FileConfigParseError::IO(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Config file {} does not exist", path.display()),
+            )));
+        }
+
+        self.prepare_config_directory()?;
+        if self.config_path_is_not_a_file() {
+            return Err(FileConfigParseError::NotAFile(
+                self.config_file_path.clone(),
+            ));
+        }
+
+        let created = !self.file_system.exists(path);
+        if created {
+            self.file_system
+                .write(path, self.initial_document()?.as_bytes())?;
+            debug_event!("Created config file {}", path.display());
+        }
+
+        let mut config_string = self.read_bounded(path)?;
+        if config_string.trim().is_empty() {
+            if self.options.empty_file_policy == EmptyFilePolicy::Error {
+                return Err(FileConfigParseError::EmptyFile(path.clone()));
+            }
+
+            warn!(
+                "Config file {} is empty, filling it with the defaults",
+                path.display()
+            );
+            config_string = self.initial_document()?;
+        }
+        let (config, document) = match self.parse_document(&config_string) {
+            Err(
+                error @ (FileConfigParseError::Codec(_)
+                | FileConfigParseError::Serde(_)
+                | FileConfigParseError::UnresolvedInterpolation(_)),
+            ) if self.options.strictness == Strictness::Lenient => {
+                warn!(
+                    "Config file {} is invalid, continuing with the defaults: {}",
+                    path.display(),
+                    error
+                );
+                // Return without saving, so the invalid config file is not overwritten
+                let (config, _) = self.parse_document(&self.initial_document()?)?;
+                if created {
+                    self.run_on_create()?;
+                }
+                return self.apply_overlays(config);
+            }
+            result => result?,
+        };
+        trace_event!(
+            "Parsed config file {} ({} bytes) in {:?}",
+            path.display(),
+            config_string.len(),
+            started.elapsed()
+        );
+        // In case the config file was missing some fields which serde used the defaults for
+        let save_result = match document {
+            // Do not copy the included values into the config file
+            Some(_) if self.options.includes.is_some() => Ok(()),
+            // Write the original document over the config, so the interpolated values do not replace their templates
+            Some(document) if self.options.interpolation => {
+                self.write_backfilled_document(&config, document)
+            }
+            _ => self.save_config(&config),
+        };
+        match save_result {
+            Err(ConfigSaveError::IO(error))
+                if self.options.readonly_tolerant && is_read_only_error(&error) =>
+            {
+                warn!(
+                    "Unable to save config file {} after loading it, continuing with the loaded config: {}",
+                    path.display(),
+                    error
+                );
+            }
+            result => result?,
+        }
+        if created {
+            self.run_on_create()?;
+        }
+
+        self.apply_overlays(config)
+    }
+
+    /// Runs the function set by [FileHandler::with_on_create], if any, for the configuration file.
+    fn run_on_create(&self) -> Result<(), FileConfigParseError> {
+        match &self.options.on_create {
+            Some(OnCreate(on_create)) => {
+                on_create(&self.config_file_path).map_err(|source| FileConfigParseError::OnCreate {
+                    path: self.config_file_path.clone(),
+                    source,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Loads the configuration from stdin, without creating the configuration directory or saving the configuration, see [FileHandler::is_stdio].
+    fn load_stdin(&self) -> Result<Config, FileConfigParseError> {
+        let max_size = self.options.max_size.unwrap_or(DEFAULT_MAX_SIZE);
+        let mut content = Vec::new();
+        match &self.options.stdio {
+            Some(stdio) => lock(&stdio.input)
+                .by_ref()
+                .take(max_size.saturating_add(1))
+                .read_to_end(&mut content)?,
+            None => io::stdin()
+                .lock()
+                .take(max_size.saturating_add(1))
+                .read_to_end(&mut content)?,
+        };
+        let mut config_string = decode_bounded(content, &self.config_file_path, max_size)?;
+
+        if config_string.trim().is_empty() {
+            if self.options.empty_file_policy == EmptyFilePolicy::Error {
+                return Err(FileConfigParseError::EmptyFile(
+                    self.config_file_path.clone(),
+                ));
+            }
+
+            warn!("Config from stdin is empty, continuing with the defaults");
+            config_string = self.initial_document()?;
+        }
+        let (config, _) = self.parse_document(&config_string)?;
+
+        self.apply_overlays(config)
+    }
+
+    /// Loads the configuration and writes it back in canonical form, e.g. for a `config fmt` command.
+    ///
+    /// The configuration is loaded like in [FileHandler::load_config], which backfills missing fields with their defaults.
+    /// Then the configuration file is rewritten with the keys of all objects sorted (regardless of [FileHandler::with_sorted_keys])
+    /// and the formatting of the codec. As the output only depends on the content, normalizing is idempotent:
+    /// normalizing a normalized file produces byte-identical output.
+    ///
+    /// Like in `load_config`, interpolation templates (see [FileHandler::with_interpolation]) are written back instead of their values,
+    /// and included files (see [FileHandler::with_includes]) are expanded into the returned configuration, but not copied into the configuration file.
+    /// Reading the configuration from stdin (see [FileHandler::is_stdio]) is not supported, as it can not be written back.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn normalize(&self) -> Result<Config, FileConfigParseError> {
+        if self.is_stdio() {
+            return Err(FileConfigParseError::IO(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Config from stdin can not be normalized, as it can not be written back",
+            )));
+        }
+
+        self.load_config()?;
+        let config_string = self.read_bounded(&self.config_file_path)?;
+        let (config, document) = self.parse_document(&config_string)?;
+
+        let mut value = match document {
+            Some(document) if self.options.includes.is_some() => document,
+            Some(document) if self.options.interpolation => {
+                let mut value = serde_json::to_value(&config)?;
+                merger::merge_values(&mut value, document);
+                value
+            }
+            _ => {
+                let mut value = serde_json::to_value(&config)?;
+                if self.options.preserve_unknown {
+                    insert_unknown_keys(&mut value, &self.lock_unknown_keys());
+                }
+                value
+            }
+        };
+        sort_keys(&mut value);
+
+        let normalized = self
+            .codec
+            .to_string(&value)
+            .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?;
+        self.write_file(&normalized)?;
+
+        self.apply_overlays(config)
+    }
+
+    /// Loads the configuration like [FileHandler::load_config], and returns it in an `Arc` to share it between threads.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance in an `Arc`.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn load_config_arc(&self) -> Result<Arc<Config>, FileConfigParseError> {
+        Ok(Arc::new(self.load_config()?))
+    }
+
+    /// Loads the configuration like [FileHandler::load_config], and records which keys were present in the configuration file.
+    ///
+    /// This allows telling values that were set explicitly apart from the ones filled in from the defaults,
+    /// e.g. to only let another layer override values that were not set in the file. See [PresenceMap].
+    /// If the configuration file did not exist, the presence map is empty.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance and the [PresenceMap].
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn load_config_with_presence(&self) -> Result<(Config, PresenceMap), FileConfigParseError> {
+        // Read the file before loading it, as loading backfills the missing keys
+        let config_string = match self.read_bounded(&self.config_file_path) {
+            Ok(config_string) => Some(config_string),
+            Err(FileConfigParseError::IO(error)) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(FileConfigParseError::IO(_)) if self.config_path_is_not_a_file() => None,
+            Err(error) => return Err(error),
+        };
+
+        let config = self.load_config()?;
+        let mut presence_map = PresenceMap::default();
+        if let Some(config_string) = config_string {
+            let document: Value = match self.codec.from_str(&config_string) {
+                Ok(document) => document,
+                // load_config continued with the defaults, so no key of the invalid file is present
+                Err(_) if self.options.strictness == Strictness::Lenient => {
+                    return Ok((config, presence_map));
+                }
+                Err(error) => return Err(FileConfigParseError::Codec(Box::new(error))),
+            };
+            let known = serde_json::to_value(&config)?;
+            collect_present_paths(&document, &known, "", &mut presence_map.paths);
+        }
+
+        Ok((config, presence_map))
+    }
+
+    /// Loads the configuration like [FileHandler::load_config], but recovers from a corrupt configuration file.
+    ///
+    /// If the configuration file can not be parsed, it is moved to `<file name>.corrupt.<timestamp>` next to it
+    /// (e.g. `config.json.corrupt.1700000000`, where the timestamp is in seconds since the Unix epoch),
+    /// and a fresh configuration file with the defaults of `Config` is created and loaded instead.
+    ///
+    /// Only a configuration file that can not be decoded by the codec at all (e.g. invalid JSON) triggers a recovery.
+    /// A configuration file that decodes, but does not match `Config` (e.g. a missing field of a type without \#[serde(default)],
+    /// or a value of the wrong type), is not considered corrupt, and the error is returned as it is.
+    /// The same applies to all other errors, e.g. IO errors or errors in included files (see [FileHandler::with_includes]).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance, and a [RecoveryInfo] if the configuration file was replaced.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn load_config_recover(
+        &self,
+    ) -> Result<(Config, Option<RecoveryInfo>), FileConfigParseError> {
+        let load_error = match self.load_config() {
+            Ok(config) => return Ok((config, None)),
+            Err(error @ FileConfigParseError::Codec(_)) => error,
+            Err(error) => return Err(error),
+        };
+        // Only recover if the configuration file itself is not decodable, not if it merely does not match `Config`
+        let config_string = self.read_bounded(&self.config_file_path)?;
+        let error = match self.codec.from_str::<Value>(&config_string) {
+            Ok(_) => return Err(load_error),
+            Err(error) => FileConfigParseError::Codec(Box::new(error)),
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut corrupt_file_name = self
+            .config_file_path
+            .file_name()
+            .unwrap_or_default()
+            .to_os_string();
+        corrupt_file_name.push(format!(".corrupt.{}", timestamp));
+        let corrupt_file_path = self.config_file_path.with_file_name(corrupt_file_name);
+
+        self.file_system
+            .rename(&self.config_file_path, &corrupt_file_path)?;
+        warn!(
+            "Config file {} is corrupt and was moved to {}: {}",
+            self.config_file_path.display(),
+            corrupt_file_path.display(),
+            error
+        );
+
+        let config = self.load_config()?;
+        let recovery_info = RecoveryInfo {
+            corrupt_file_path,
+            error,
+        };
+
+        Ok((config, Some(recovery_info)))
+    }
+
+    /// Loads the configuration, and merges the fragments of the `conf.d` subdirectory of the configuration directory onto it.
+    ///
+    /// The base configuration is loaded by [FileHandler::load_config] first.
+    /// Then, every file in `conf.d` with the same extension as the configuration file (e.g. `.json` for `config.json`) is parsed as a partial document.
+    /// Files with another extension and subdirectories are ignored.
+    ///
+    /// Fragments are merged in lexical order of their file names (compared byte-wise), so `10-network.json` is applied before `20-logging.json`.
+    /// Objects are merged recursively. Any other value of a later fragment replaces the value of the base configuration or an earlier fragment.
+    /// Fragments are never written back, and the configuration file only ever contains the base configuration.
+    ///
+    /// If `conf.d` does not exist, this is equivalent to [FileHandler::load_config].
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the merged Config instance.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn load_directory(&self) -> Result<Config, FileConfigParseError> {
+        let config = self.load_config()?;
+
+        let fragments_path = self.config_directory_path.join("conf.d");
+        let entries = match self.file_system.read_dir(&fragments_path) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(config),
+            Err(error) => return Err(error.into()),
+        };
+
+        let extension = self.config_file_path.extension();
+        let mut fragment_paths = Vec::new();
+        for path in entries {
+            if path.extension() == extension && self.file_system.metadata(&path)?.is_file {
+                fragment_paths.push(path);
+            }
+        }
+        fragment_paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+        let mut document = serde_json::to_value(&config)?;
+        for fragment_path in fragment_paths {
+            let fragment_string = self.read_bounded(&fragment_path)?;
+            let fragment: Value = self
+                .codec
+                .from_str(&fragment_string)
+                .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?;
+            merger::merge_values(&mut document, fragment);
+        }
+
+        Ok(serde_json::from_value(document)?)
+    }
+
+    /// Returns the content of a freshly created configuration file, see [FileHandler::with_bootstrap].
+    fn initial_document(&self) -> Result<String, FileConfigParseError> {
+        match &self.options.bootstrap {
+            Some(Bootstrap(bootstrap)) => Ok(bootstrap()),
+            None => self
+                .codec
+                .to_string(&BTreeMap::<String, String>::new())
+                .map_err(|error| FileConfigParseError::Codec(Box::new(error))),
+        }
+    }
+
+    /// Parses the content of a configuration file.
+    ///
+    /// The parsed document is returned as well, if it was needed to preserve unknown keys or to interpolate values.
+    fn parse_document(
+        &self,
+        config_string: &str,
+    ) -> Result<(Config, Option<Value>), FileConfigParseError> {
+        if !self.options.preserve_unknown
+            && !self.options.interpolation
+            && self.options.aliases.is_empty()
+            && self.options.includes.is_none()
+        {
+            let config = self
+                .codec
+                .from_str(config_string)
+                .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?;
+
+            return Ok((config, None));
+        }
+
+        let mut document: Value = self
+            .codec
+            .from_str(config_string)
+            .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?;
+        self.apply_aliases(&mut document);
+        let mut resolved = match &self.options.includes {
+            Some(key) => {
+                let mut ancestors = vec![canonical_path(&self.config_file_path)];
+                self.resolve_includes(
+                    key,
+                    document.clone(),
+                    &self.config_file_path,
+                    &mut ancestors,
+                )?
+            }
+            None => document.clone(),
+        };
+        if self.options.interpolation {
+            interpolate(&mut resolved)?;
+        }
+
+        let config = serde_json::from_value(resolved)?;
+        if self.options.preserve_unknown {
+            let known = serde_json::to_value(&config)?;
+            *self.lock_unknown_keys() = extract_unknown_keys(&document, &known);
+        }
+
+        Ok((config, Some(document)))
+    }
+
+    /// Renames the deprecated top-level keys of `document` to their new keys, see [FileHandler::with_aliases].
+    fn apply_aliases(&self, document: &mut Value) {
+        let Value::Object(object) = document else {
+            return;
+        };
+
+        for (old_key, new_key) in &self.options.aliases {
+            let Some(value) = object.remove(old_key) else {
+                continue;
+            };
+
+            if object.contains_key(new_key) {
+                warn!(
+                    "Config key {} in {} is deprecated and shadowed by {}",
+                    old_key,
+                    self.config_file_path.display(),
+                    new_key
+                );
+            } else {
+                warn!(
+                    "Config key {} in {} is deprecated, use {} instead",
+                    old_key,
+                    self.config_file_path.display(),
+                    new_key
+                );
+                object.insert(new_key.clone(), value);
+            }
+        }
+    }
+
+    /// Merges the files included by `document` beneath it, see [FileHandler::with_includes].
+    ///
+    /// `ancestors` holds the canonical paths of the files currently being included, to detect cycles.
+    fn resolve_includes(
+        &self,
+        key: &str,
+        document: Value,
+        path: &Path,
+        ancestors: &mut Vec<PathBuf>,
+    ) -> Result<Value, FileConfigParseError> {
+        let Value::Object(mut object) = document else {
+            return Ok(document);
+        };
+        let Some(includes) = object.remove(key) else {
+            return Ok(Value::Object(object));
+        };
+        let includes: Vec<PathBuf> = serde_json::from_value(includes)?;
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut merged = Value::Object(Map::new());
+        for include in includes {
+            let include_path = directory.join(include);
+            let canonical_path = canonical_path(&include_path);
+            if ancestors.len() > MAX_INCLUDE_DEPTH || ancestors.contains(&canonical_path) {
+                return Err(FileConfigParseError::IncludeCycle(include_path));
+            }
+
+            let included = self
+                .read_bounded(&include_path)
+                .and_then(|content| {
+                    self.codec
+                        .from_str(&content)
+                        .map_err(|error| FileConfigParseError::Codec(Box::new(error)))
+                })
+                .map_err(|source| FileConfigParseError::Include {
+                    path: include_path.clone(),
+                    source: Box::new(source),
+                })?;
+            ancestors.push(canonical_path);
+            let included = self.resolve_includes(key, included, &include_path, ancestors)?;
+            ancestors.pop();
+
+            merger::merge_values(&mut merged, included);
+        }
+        merger::merge_values(&mut merged, Value::Object(object));
+
+        Ok(merged)
+    }
+
+    /// Returns the path of the configuration file of `profile`, see [FileHandler::list_profiles].
+    fn profile_file_path(&self, profile: &str) -> PathBuf {
+        let stem = self.config_file_path.file_stem().unwrap_or_default();
+        let mut file_name = stem.to_os_string();
+        file_name.push(".");
+        file_name.push(profile);
+        if let Some(extension) = self.config_file_path.extension() {
+            file_name.push(".");
+            file_name.push(extension);
+        }
+
+        self.config_file_path.with_file_name(file_name)
+    }
+
+    /// Merges the overlays of [FileHandler::with_conditional_overlays] onto `config`.
+    fn apply_overlays(&self, config: Config) -> Result<Config, FileConfigParseError> {
+        let Some(target) = &self.options.overlay_target else {
+            return Ok(config);
+        };
+
+        let profiles = std::iter::once(target.platform.as_str()).chain(target.hostname.as_deref());
+        let mut value = None;
+        for profile in profiles {
+            let overlay_path = self.profile_file_path(profile);
+            let overlay_string = match self.read_bounded(&overlay_path) {
+                Ok(overlay_string) => overlay_string,
+                Err(FileConfigParseError::IO(error)) if error.kind() == io::ErrorKind::NotFound => {
+                    continue
+                }
+                Err(error) => return Err(error),
+            };
+
+            let mut overlay: Value = self
+                .codec
+                .from_str(&overlay_string)
+                .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?;
+            if self.options.interpolation {
+                interpolate(&mut overlay)?;
+            }
+
+            let value = match &mut value {
+                Some(value) => value,
+                None => value.insert(serde_json::to_value(&config)?),
+            };
+            merger::merge_values(value, overlay);
+        }
+
+        match value {
+            Some(value) => Ok(serde_json::from_value(value)?),
+            None => Ok(config),
+        }
+    }
+
+    /// Writes `document` merged over `config`, which adds the fields that are missing in `document` without changing the existing ones.
+    fn write_backfilled_document(
+        &self,
+        config: &Config,
+        document: Value,
+    ) -> Result<(), ConfigSaveError> {
+        let mut value = serde_json::to_value(config)?;
+        merger::merge_values(&mut value, document);
+        if self.options.sorted_keys {
+            sort_keys(&mut value);
+        }
+
+        let config_string = self
+            .codec
+            .to_string(&value)
+            .map_err(|error| ConfigSaveError::Codec(Box::new(error)))?;
+        self.write_file(&config_string)?;
+
+        Ok(())
+    }
+
+    /// Writes `content` to the configuration file, applying [FileHandler::with_retry] and [FileHandler::with_durable].
+    fn write_file(&self, content: &str) -> Result<(), io::Error> {
+        if !self.options.durable {
+            return self.options.retry.run(|| {
+                self.file_system
+                    .write(&self.config_file_path, content.as_bytes())
+            });
+        }
+
+        let temporary_file_path = self.temporary_file_path();
+        self.options.retry.run(|| {
+            self.file_system
+                .write(&temporary_file_path, content.as_bytes())?;
+            self.file_system.sync(&temporary_file_path)?;
+            self.file_system
+                .rename(&temporary_file_path, &self.config_file_path)
+        })?;
+
+        #[cfg(unix)]
+        if let Err(error) = self.file_system.sync(&self.config_directory_path) {
+            warn!(
+                "Unable to sync config directory {}, the saved config file may not survive a power loss: {}",
+                self.config_directory_path.display(),
+                error
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Returns the path of the temporary file of durable writes, honoring [FileHandler::with_temp_dir].
+    fn temporary_file_path(&self) -> PathBuf {
+        let mut temporary_file_name = OsString::from(".");
+        temporary_file_name.push(self.config_file_path.file_name().unwrap_or_default());
+        temporary_file_name.push(".tmp");
+
+        let Some(temp_dir) = &self.options.temp_dir else {
+            return self.config_file_path.with_file_name(temporary_file_name);
+        };
+
+        let device = |path: &Path| {
+            self.file_system
+                .metadata(path)
+                .ok()
+                .filter(|metadata| !metadata.is_file)
+                .and_then(|metadata| metadata.device)
+        };
+        match (device(temp_dir), device(&self.config_directory_path)) {
+            (Some(temp_device), Some(config_device)) if temp_device == config_device => {
+                return temp_dir.join(temporary_file_name);
+            }
+            (Some(_), Some(_)) => warn!(
+                "Temp directory {} is on another filesystem than config directory {}, writing the temporary file next to the config file",
+                temp_dir.display(),
+                self.config_directory_path.display()
+            ),
+            _ => warn!(
+                "Unable to determine whether temp directory {} is on the same filesystem as config directory {}, writing the temporary file next to the config file",
+                temp_dir.display(),
+                self.config_directory_path.display()
+            ),
+        }
+
+        self.config_file_path.with_file_name(temporary_file_name)
+    }
+
+    /// Reads the configuration file like [FileHandler::read_bounded], returning `None` if it does not exist.
+    ///
+    /// A file exceeding [FileHandler::with_max_size] results in an `io::ErrorKind::InvalidData` error.
+    fn read_config_file_if_exists(&self) -> Result<Option<String>, io::Error> {
+        match self.read_bounded(&self.config_file_path) {
+            Ok(config_string) => Ok(Some(config_string)),
+            Err(FileConfigParseError::IO(error)) if error.kind() == io::ErrorKind::NotFound => {
+                Ok(None)
+            }
+            Err(FileConfigParseError::IO(error)) => Err(error),
+            Err(error) => Err(io::Error::new(io::ErrorKind::InvalidData, error)),
+        }
+    }
+
+    /// Reads the file at `path`, but at most [FileHandler::with_max_size] bytes.
+    fn read_bounded(&self, path: &Path) -> Result<String, FileConfigParseError> {
+        let max_size = self.options.max_size.unwrap_or(DEFAULT_MAX_SIZE);
+
+        let content = self.file_system.read(path, max_size.saturating_add(1))?;
+        decode_bounded(content, path, max_size)
+    }
+
+    /// Returns whether something other than a file (e.g. a directory created by a misconfigured volume mount) exists at the config file path.
+    fn config_path_is_not_a_file(&self) -> bool {
+        self.file_system
+            .metadata(&self.config_file_path)
+            .is_ok_and(|metadata| !metadata.is_file)
+    }
+
+    fn lock_unknown_keys(&self) -> MutexGuard<'_, Map<String, Value>> {
+        self.unknown_keys
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<Config, FileCodec, FileStorage> ConfigStore<Config>
+    for FileHandler<Config, FileCodec, FileStorage>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    FileCodec: Codec,
+    FileStorage: FileSystem,
+{
+    fn load(&self) -> Result<Config, FileConfigParseError> {
+        self.load_config()
+    }
+
+    fn save(&self, config: &Config) -> Result<(), ConfigSaveError> {
+        self.save_config(config)
+    }
+
+    fn exists(&self) -> Result<bool, io::Error> {
+        match self.file_system.metadata(&self.config_file_path) {
+            Ok(_) => Ok(true),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn delete(&self) -> Result<(), io::Error> {
+        match self.file_system.remove(&self.config_file_path) {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+/// Resolves the configuration directory of an application, like [FileHandler::new] does, without creating it.
+///
+/// Unlike [FileHandler::new], this does not need a configuration type, which makes it handy for tooling that only needs the path.
+///
+/// # Arguments
+///
+/// * `app_name` - The name of the application, which is appended to the base directory.
+/// * `config_directory` - An optional custom base directory. Defaults to the OS-specific configuration directory.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the path of the configuration directory.
+/// * Failure is indicated by an `Err` value, containing a `ConfigPathError`.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+/// use lum_config::file_handler::resolve_config_dir;
+///
+/// let path = resolve_config_dir("MyApp", Some("/etc")).unwrap();
+///
+/// assert_eq!(path, Path::new("/etc/MyApp"));
+/// ```
+pub fn resolve_config_dir(
+    app_name: &str,
+    config_directory: Option<&str>,
+) -> Result<PathBuf, ConfigPathError> {
+    let mut config_directory_path = match config_directory {
+        Some(config_directory) => PathBuf::from(config_directory),
+        None => match dirs::config_dir() {
+            Some(path) => path,
+            None => return Err(ConfigPathError::UnknownConfigDirectory),
+        },
+    };
+    config_directory_path.push(app_name);
+
+    Ok(config_directory_path)
+}
+
+/// Serializes `config` to pretty-printed JSON for display, using the default [JsonCodec].
+///
+/// To format a configuration with the codec and options of a specific handler, use [FileHandler::format_config].
+///
+/// # Arguments
+///
+/// * `config` - The configuration to be formatted.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the pretty-printed `String`.
+/// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::Serialize;
+/// use lum_config::file_handler::to_pretty_string;
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     name: String,
+///     port: u16,
+/// }
+///
+/// let config = Config {
+///     name: "lum".to_string(),
+///     port: 8080,
+/// };
+///
+/// let output = to_pretty_string(&config).unwrap();
+/// println!("{}", output);
+///
+/// assert_eq!(output, "{\n  \"name\": \"lum\",\n  \"port\": 8080\n}");
+/// ```
+pub fn to_pretty_string<Config: Serialize>(config: &Config) -> Result<String, ConfigSaveError> {
+    JsonCodec::default()
+        .to_string(config)
+        .map_err(|error| ConfigSaveError::Codec(Box::new(error)))
+}
+
+/// Lazily loads records from a JSON Lines file, where each line holds one JSON value.
+///
+/// Unlike [FileHandler::load_config], the file is not read up front: the returned iterator reads and deserializes one line at a time,
+/// so large lists of records do not have to fit into memory at once. Blank lines are skipped.
+///
+/// A line that can not be deserialized yields `FileConfigParseError::InvalidRecord`, naming the line, and the iterator continues with the next line.
+/// To abort at the first invalid record instead, collect into a `Result`, e.g. `Result<Vec<Record>, _>`.
+/// If the file can not be opened or read, a `FileConfigParseError::IO` is yielded and the iterator ends.
+///
+/// # Arguments
+///
+/// * `path` - The path of the JSON Lines file.
+///
+/// # Returns
+///
+/// An iterator over the records of the file, or the errors that occurred while reading them.
+///
+/// # Examples
+///
+/// ```
+/// use std::{env, fs};
+/// use lum_libs::serde::Deserialize;
+/// use lum_config::file_handler::load_ndjson;
+///
+/// #[derive(Deserialize)]
+/// struct Feed {
+///     url: String,
+/// }
+///
+/// let path = env::temp_dir().join("lum_config_feeds.jsonl");
+/// fs::write(&path, "{\"url\": \"a\"}\n\n{\"url\": 1}\n{\"url\": \"b\"}\n").unwrap();
+///
+/// let records: Vec<_> = load_ndjson::<Feed, _>(&path).collect();
+/// assert_eq!(records.len(), 3);
+/// assert_eq!(records[0].as_ref().unwrap().url, "a");
+/// assert!(records[1].is_err());
+/// assert_eq!(records[2].as_ref().unwrap().url, "b");
+///
+/// fs::remove_file(path).unwrap();
+/// ```
+pub fn load_ndjson<Record, IntoPath>(
+    path: IntoPath,
+) -> impl Iterator<Item = Result<Record, FileConfigParseError>>
+where
+    Record: for<'de> Deserialize<'de>,
+    IntoPath: AsRef<Path>,
+{
+    let (lines, error) = match File::open(path) {
+        Ok(file) => (Some(BufReader::new(file).lines()), None),
+        Err(error) => (None, Some(error)),
+    };
+
+    NdjsonRecords {
+        lines,
+        error,
+        line: 0,
+        _phantom_record: PhantomData,
+    }
+}
+
+/// The iterator returned by [load_ndjson].
+struct NdjsonRecords<Record> {
+    lines: Option<Lines<BufReader<File>>>,
+    error: Option<io::Error>,
+    line: usize,
+    _phantom_record: PhantomData<Record>,
+}
+
+impl<Record> Iterator for NdjsonRecords<Record>
+where
+    Record: for<'de> Deserialize<'de>,
+{
+    type Item = Result<Record, FileConfigParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error.into()));
+        }
+
+        loop {
+            let content = match self.lines.as_mut()?.next()? {
+                Ok(content) => content,
+                Err(error) => {
+                    self.lines = None;
+                    return Some(Err(error.into()));
+                }
+            };
+            self.line += 1;
+
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            let line = self.line;
+            return Some(
+                serde_json::from_str(&content)
+                    .map_err(|source| FileConfigParseError::InvalidRecord { line, source }),
+            );
+        }
+    }
+}
+
+/// Serializes `config` to compact JSON, with the values at the given paths replaced by `"***"`, e.g. to log a configuration without leaking secrets.
+///
+/// Paths separate object keys with `.`, e.g. `database.password`. A numeric segment indexes into an array, e.g. `tokens.0`.
+/// Paths that do not exist in the serialized configuration are ignored.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to be serialized.
+/// * `secret_paths` - The paths of the values to be masked.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the redacted JSON `String`.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::Serialize;
+/// use lum_config::file_handler::to_redacted_string;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     user: String,
+///     password: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     database: Database,
+/// }
+///
+/// let config = Config {
+///     database: Database {
+///         user: "lum".to_string(),
+///         password: "hunter2".to_string(),
+///     },
+/// };
+///
+/// let output = to_redacted_string(&config, &["database.password"]).unwrap();
+/// assert_eq!(output, r#"{"database":{"password":"***","user":"lum"}}"#);
+/// ```
+pub fn to_redacted_string<Config: Serialize>(
+    config: &Config,
+    secret_paths: &[&str],
+) -> Result<String, serde_json::Error> {
+    let mut value = serde_json::to_value(config)?;
+    for secret_path in secret_paths {
+        if let Some(secret) = value_at_path(&mut value, secret_path) {
+            *secret = Value::String("***".to_string());
+        }
+    }
+
+    serde_json::to_string(&value)
+}
+
+/// Reads a single value of `config` by its path, e.g. for a generic settings UI.
+///
+/// The configuration is serialized to a `serde_json::Value`, the path is followed, and the value found there is deserialized into `Target`.
+/// Paths separate object keys with `.`, e.g. `database.port`. A numeric segment indexes into an array, e.g. `hosts.0`.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to read from.
+/// * `path` - The path of the value.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the value at `path`.
+/// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+///   `FileConfigParseError::NotFound` is returned if there is no value at `path`,
+///   and `FileConfigParseError::Serde` if the value can not be deserialized into `Target`.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::Serialize;
+/// use lum_config::{file_handler::get_path, FileConfigParseError};
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     port: u16,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     hosts: Vec<String>,
+///     database: Database,
+/// }
+///
+/// let config = Config {
+///     hosts: vec!["a".to_string()],
+///     database: Database { port: 5432 },
+/// };
+///
+/// assert_eq!(get_path::<u16, _>(&config, "database.port").unwrap(), 5432);
+/// assert_eq!(get_path::<String, _>(&config, "hosts.0").unwrap(), "a");
+/// assert!(matches!(
+///     get_path::<u16, _>(&config, "database.user"),
+///     Err(FileConfigParseError::NotFound(_))
+/// ));
+/// ```
+pub fn get_path<Target, Config>(config: &Config, path: &str) -> Result<Target, FileConfigParseError>
+where
+    Target: for<'de> Deserialize<'de>,
+    Config: Serialize,
+{
+    let mut document = serde_json::to_value(config)?;
+    match value_at_path(&mut document, path) {
+        Some(value) => Ok(serde_json::from_value(value.take())?),
+        None => Err(FileConfigParseError::NotFound(path.to_string())),
+    }
+}
+
+/// Follows the dotted `path` into `value`, see [get_path].
+fn value_at_path<'value>(value: &'value mut Value, path: &str) -> Option<&'value mut Value> {
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Object(object) => object.get_mut(segment),
+            Value::Array(array) => segment
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| array.get_mut(index)),
+            _ => None,
+        })
+}
+
+/// Flattens `config` into key/value pairs, one for every leaf, e.g. for a `config dump --flat` command or to diff configurations.
+///
+/// The configuration is serialized to a `serde_json::Value`, which is walked recursively.
+/// Keys are the paths of the leaves, with the object keys and array indices joined by `separator`, e.g. `database.hosts.0`.
+/// String leaves are rendered as they are, all other leaves as their JSON text, e.g. `8080`, `true` or `null`.
+/// Empty objects and arrays are leaves as well, rendered as `{}` and `[]`, so they do not disappear from the output.
+///
+/// # Arguments
+///
+/// * `config` - The configuration to flatten.
+/// * `separator` - The separator between the segments of a key, e.g. `.` or `_`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the flattened key/value pairs, sorted by key.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::Serialize;
+/// use lum_config::file_handler::flatten;
+///
+/// #[derive(Serialize)]
+/// struct Database {
+///     hosts: Vec<String>,
+///     port: u16,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Config {
+///     database: Database,
+/// }
+///
+/// let config = Config {
+///     database: Database {
+///         hosts: vec!["a".to_string(), "b".to_string()],
+///         port: 5432,
+///     },
+/// };
+///
+/// let flat = flatten(&config, ".").unwrap();
+/// assert_eq!(flat["database.hosts.1"], "b");
+/// assert_eq!(flat["database.port"], "5432");
+/// ```
+pub fn flatten<Config: Serialize>(
+    config: &Config,
+    separator: &str,
+) -> Result<BTreeMap<String, String>, serde_json::Error> {
+    let value = serde_json::to_value(config)?;
+    let mut entries = BTreeMap::new();
+    flatten_value(&value, None, separator, &mut entries);
+
+    Ok(entries)
+}
+
+/// Collects the leaves of `value` into `entries`, prefixing their keys with `prefix`, see [flatten].
+fn flatten_value(
+    value: &Value,
+    prefix: Option<&str>,
+    separator: &str,
+    entries: &mut BTreeMap<String, String>,
+) {
+    let key = |segment: &str| match prefix {
+        Some(prefix) => format!("{prefix}{separator}{segment}"),
+        None => segment.to_string(),
+    };
+
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (segment, nested) in object {
+                flatten_value(nested, Some(&key(segment)), separator, entries);
+            }
+        }
+        Value::Array(array) if !array.is_empty() => {
+            for (index, nested) in array.iter().enumerate() {
+                flatten_value(nested, Some(&key(&index.to_string())), separator, entries);
+            }
+        }
+        leaf => {
+            let rendered = match leaf {
+                Value::String(string) => string.clone(),
+                leaf => leaf.to_string(),
+            };
+            entries.insert(prefix.unwrap_or_default().to_string(), rendered);
+        }
+    }
+}
+
+/// Decodes the content read from `path` as UTF-8, after checking that it does not exceed `max_size` bytes.
+///
+/// The size is checked first, as content that was cut at the limit may end with an incomplete character.
+fn decode_bounded(
+    content: Vec<u8>,
+    path: &Path,
+    max_size: u64,
+) -> Result<String, FileConfigParseError> {
+    if content.len() as u64 > max_size {
+        return Err(FileConfigParseError::TooLarge {
+            path: path.to_path_buf(),
+            max_size,
+        });
+    }
+
+    String::from_utf8(content).map_err(|error| {
+        FileConfigParseError::IO(io::Error::new(io::ErrorKind::InvalidData, error))
+    })
+}
+
+/// Locks `mutex`, ignoring poisoning, as the streams of [Stdio] stay usable after a panic.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the canonical form of `path`, or `path` itself if it can not be canonicalized.
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Returns whether `error` was caused by a read-only filesystem or missing permissions.
+pub(crate) fn is_read_only_error(error: &io::Error) -> bool {
+    // EROFS, which has the same value on Linux and macOS. `io::ErrorKind::ReadOnlyFilesystem` requires Rust 1.83.
+    #[cfg(unix)]
+    if error.raw_os_error() == Some(30) {
+        return true;
+    }
+
+    error.kind() == io::ErrorKind::PermissionDenied
+}
+
+/// Interpolates the environment variables referenced in all strings of `value`, including strings nested in objects and arrays.
+fn interpolate(value: &mut Value) -> Result<(), FileConfigParseError> {
+    match value {
+        Value::String(string) => *string = interpolate_string(string)?,
+        Value::Object(object) => object.values_mut().try_for_each(interpolate)?,
+        Value::Array(array) => array.iter_mut().try_for_each(interpolate)?,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Substitutes `${VAR}`, `${VAR:-default}`, `$VAR` and `$$` in `input`, see [FileHandler::with_interpolation].
+fn interpolate_string(input: &str) -> Result<String, FileConfigParseError> {
+    let is_name_start = |character: char| character.is_ascii_alphabetic() || character == '_';
+    let is_name = |character: char| character.is_ascii_alphanumeric() || character == '_';
+
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(position) = rest.find('$') {
+        output.push_str(&rest[..position]);
+        let after = &rest[position + 1..];
+
+        if let Some(after) = after.strip_prefix('$') {
+            output.push('$');
+            rest = after;
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let Some(end) = braced.find('}') else {
+                output.push('$');
+                rest = after;
+                continue;
+            };
+
+            let expression = &braced[..end];
+            let (name, default) = match expression.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expression, None),
+            };
+            let is_valid_name = name.starts_with(is_name_start) && name.chars().all(is_name);
+            if !is_valid_name {
+                output.push('$');
+                rest = after;
+                continue;
+            }
+
+            match (env::var(name), default) {
+                (Ok(value), _) => output.push_str(&value),
+                (Err(_), Some(default)) => output.push_str(default),
+                (Err(_), None) => {
+                    return Err(FileConfigParseError::UnresolvedInterpolation(
+                        name.to_string(),
+                    ))
+                }
+            }
+            rest = &braced[end + 1..];
+        } else if after.starts_with(is_name_start) {
+            let end = after
+                .find(|character| !is_name(character))
+                .unwrap_or(after.len());
+            let name = &after[..end];
+            let value = env::var(name)
+                .map_err(|_| FileConfigParseError::UnresolvedInterpolation(name.to_string()))?;
+            output.push_str(&value);
+            rest = &after[end..];
+        } else {
+            output.push('$');
+            rest = after;
+        }
+    }
+    output.push_str(rest);
+
+    Ok(output)
+}
+
+/// Collects the paths of all keys of `document` that are present in `known` as well, descending into objects present in both.
+fn collect_present_paths(
+    document: &Value,
+    known: &Value,
+    prefix: &str,
+    paths: &mut BTreeSet<String>,
+) {
+    let (Value::Object(document), Value::Object(known)) = (document, known) else {
+        return;
+    };
+
+    for (key, value) in document {
+        let Some(known_value) = known.get(key) else {
+            continue;
+        };
+
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        collect_present_paths(value, known_value, &path, paths);
+        paths.insert(path);
+    }
+}
+
+/// Collects all keys of `document` that are missing in `known`, descending into objects present in both.
+fn extract_unknown_keys(document: &Value, known: &Value) -> Map<String, Value> {
+    let mut unknown_keys = Map::new();
+    let (Value::Object(document), Value::Object(known)) = (document, known) else {
+        return unknown_keys;
+    };
+
+    for (key, value) in document {
+        match known.get(key) {
+            None => {
+                unknown_keys.insert(key.clone(), value.clone());
+            }
+            Some(known_value) => {
+                let nested = extract_unknown_keys(value, known_value);
+                if !nested.is_empty() {
+                    unknown_keys.insert(key.clone(), Value::Object(nested));
+                }
+            }
+        }
+    }
+
+    unknown_keys
+}
+
+/// Inserts all `unknown_keys` into `value` that it does not already contain, descending into objects present in both.
+fn insert_unknown_keys(value: &mut Value, unknown_keys: &Map<String, Value>) {
+    let Value::Object(object) = value else {
+        return;
+    };
+
+    for (key, unknown_value) in unknown_keys {
+        match (object.get_mut(key), unknown_value) {
+            (None, _) => {
+                object.insert(key.clone(), unknown_value.clone());
+            }
+            (Some(existing), Value::Object(nested)) => insert_unknown_keys(existing, nested),
+            (Some(_), _) => {}
+        }
+    }
+}
+
+/// Recursively sorts the keys of all objects in `value`, including objects nested in arrays.
+fn sort_keys(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            let mut entries: Vec<(String, Value)> = std::mem::take(object).into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            for (key, mut nested) in entries {
+                sort_keys(&mut nested);
+                object.insert(key, nested);
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(sort_keys),
+        _ => {}
+    }
+}
+
+/// Computes the 64-bit FNV-1a hash of `bytes`, which, unlike `std::hash::DefaultHasher`, is stable across Rust versions.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
 }
@@ -0,0 +1,185 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The metadata of a filesystem entry, see [FileSystem::metadata].
+///
+/// # Fields
+///
+/// * `is_file` - Whether the entry is a regular file.
+/// * `len` - The size of the entry in bytes.
+/// * `readonly` - Whether the entry is read-only, like `std::fs::Permissions::readonly`.
+/// * `device` - The ID of the device (filesystem) containing the entry, or `None` if it is not available on the platform.
+/// * `modified` - The last modification time of the entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub is_file: bool,
+    pub len: u64,
+    pub readonly: bool,
+    pub device: Option<u64>,
+    pub modified: SystemTime,
+}
+
+/// A trait that defines the filesystem operations used by [crate::FileHandler].
+///
+/// [StdFs] implements it on top of `std::fs`, and is used by default.
+/// Implement it to test a `FileHandler` without touching the real filesystem, e.g. with an in-memory filesystem
+/// that records the operations, see [crate::FileHandler::with_file_system].
+///
+/// Errors should use the same `io::ErrorKind`s as `std::fs`, as the `FileHandler` relies on them,
+/// most importantly `io::ErrorKind::NotFound` for missing entries and `io::ErrorKind::AlreadyExists` in [FileSystem::write_new].
+pub trait FileSystem {
+    /// Reads at most `limit` bytes of the file at `path`.
+    ///
+    /// The content is not decoded, as cutting it at `limit` may split a multibyte character.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the bytes that were read.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn read(&self, path: &Path, limit: u64) -> Result<Vec<u8>, io::Error>;
+
+    /// Writes `contents` to the file at `path`, creating it if it does not exist, and replacing its content if it does.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error>;
+
+    /// Writes `contents` to a new file at `path`, checking for its existence and creating it in a single atomic operation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`. If the file already exists, the kind is `io::ErrorKind::AlreadyExists`.
+    fn write_new(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error>;
+
+    /// Flushes the file or directory at `path` to the storage device, like `File::sync_all`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn sync(&self, path: &Path) -> Result<(), io::Error>;
+
+    /// Creates the directory at `path` and all of its missing parents.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn create_dir_all(&self, path: &Path) -> Result<(), io::Error>;
+
+    /// Returns the metadata of the entry at `path`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the [FileMetadata].
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn metadata(&self, path: &Path) -> Result<FileMetadata, io::Error>;
+
+    /// Returns whether an entry exists at `path`.
+    fn exists(&self, path: &Path) -> bool {
+        self.metadata(path).is_ok()
+    }
+
+    /// Lists the entries of the directory at `path`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the paths of the entries, in no particular order.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, io::Error>;
+
+    /// Renames the entry at `from` to `to`, replacing `to` if it exists.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), io::Error>;
+
+    /// Removes the file at `path`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing an `io::Error`.
+    fn remove(&self, path: &Path) -> Result<(), io::Error>;
+}
+
+/// The [FileSystem] backed by `std::fs`, used by [crate::FileHandler] by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StdFs;
+
+impl FileSystem for StdFs {
+    fn read(&self, path: &Path, limit: u64) -> Result<Vec<u8>, io::Error> {
+        let mut content = Vec::new();
+        File::open(path)?.take(limit).read_to_end(&mut content)?;
+
+        Ok(content)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        fs::write(path, contents)
+    }
+
+    fn write_new(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)?
+            .write_all(contents)
+    }
+
+    fn sync(&self, path: &Path) -> Result<(), io::Error> {
+        File::open(path)?.sync_all()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), io::Error> {
+        fs::create_dir_all(path)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata, io::Error> {
+        let metadata = fs::metadata(path)?;
+        #[cfg(unix)]
+        let device = Some(std::os::unix::fs::MetadataExt::dev(&metadata));
+        #[cfg(not(unix))]
+        let device = None;
+
+        Ok(FileMetadata {
+            is_file: metadata.is_file(),
+            len: metadata.len(),
+            readonly: metadata.permissions().readonly(),
+            device,
+            modified: metadata.modified()?,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect()
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), io::Error> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), io::Error> {
+        fs::remove_file(path)
+    }
+}
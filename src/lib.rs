@@ -1,17 +1,69 @@
-use lum_libs::serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf, sync::Arc};
+
+use lum_libs::{
+    log::warn,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+use crate::file_handler::is_read_only_error;
+
+/// Emits a debug event through the `log` facade, if the `log` feature is enabled. Never pass configuration values to it.
+macro_rules! debug_event {
+    ($($argument:tt)*) => {
+        #[cfg(feature = "log")]
+        lum_libs::log::debug!($($argument)*);
+    };
+}
+
+/// Emits a trace event through the `log` facade, if the `log` feature is enabled. Never pass configuration values to it.
+macro_rules! trace_event {
+    ($($argument:tt)*) => {
+        #[cfg(feature = "log")]
+        lum_libs::log::trace!($($argument)*);
+    };
+}
+
+/// Codecs for reading and writing configuration files.
+pub mod codec;
+/// Serde helpers for human-readable configuration values, like durations and byte sizes.
+#[cfg(feature = "human-values")]
+pub mod config_serde;
+/// A common interface for configuration stores.
+pub mod config_store;
+/// A marker trait for configuration types.
+pub mod config_type;
 /// Environment-related configuration handling.
 pub mod env_handler;
 /// Error types used across the crate.
 pub mod error;
 /// File-related configuration handling.
 pub mod file_handler;
+/// The filesystem abstraction used by [FileHandler].
+pub mod file_system;
+/// In-memory configuration handling, e.g. for tests.
+pub mod memory_handler;
 /// Traits and helper functions for merging configurations.
 pub mod merger;
+/// Retrying of IO operations that failed due to transient errors.
+pub mod retry;
+/// A configuration shared between threads, which can be replaced atomically.
+pub mod shared_config;
 
-pub use env_handler::EnvHandler;
+pub use codec::{Codec, JsonCodec, JsonIndent, JsonStyle};
+pub use config_store::ConfigStore;
+pub use config_type::ConfigType;
+pub use env_handler::{EnvHandler, EnvHandlerBuilder, EnvVarDoc};
 pub use error::*;
-pub use file_handler::FileHandler;
+pub use file_handler::{
+    Diagnostics, EmptyFilePolicy, FileHandler, OverlayTarget, PathStatus, PresenceMap,
+    RecoveryInfo, Strictness,
+};
+pub use file_system::{FileMetadata, FileSystem, StdFs};
+pub use memory_handler::InMemoryHandler;
 pub use merger::*;
+pub use retry::RetryPolicy;
+pub use shared_config::SharedConfig;
 
 /// Loads configurations from environment variables and a file, and merges them together.
 /// This function is a convenience function that combines the functionality of [EnvHandler], [FileHandler], and [merger].
@@ -37,6 +89,255 @@ pub fn load<IntoString: Into<String>, FileConfig, EnvConfig>(
     config_directory: Option<IntoString>,
     config_file_name: Option<IntoString>,
 ) -> Result<FileConfig, ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig>,
+    EnvConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    let (merged_config, _) =
+        load_with_metadata::<_, _, EnvConfig>(app_name, config_directory, config_file_name)?;
+
+    Ok(merged_config)
+}
+
+/// Loads configurations from environment variables and a file, and merges them together, like [load].
+/// The merged configuration is returned in an `Arc`, to share it between threads. See [SharedConfig] to replace it on reload.
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler] and [FileHandler].
+/// * `config_directory` - The configuration directory, provided to [FileHandler].
+/// * `config_file_name` - The configuration file name, provided to [FileHandler].
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the merged `FileConfig` in an `Arc`.
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_arc<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+) -> Result<Arc<FileConfig>, ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig>,
+    EnvConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    let merged_config = load::<_, _, EnvConfig>(app_name, config_directory, config_file_name)?;
+
+    Ok(Arc::new(merged_config))
+}
+
+/// Loads a configuration from a file, and overrides it with the environment variables that are set.
+///
+/// Unlike [load], a single `Config` type is used for both sources, and the environment layer is sparse (see [EnvHandler::load_sparse]):
+/// a field is only overridden if its environment variable is set, so an unset variable never replaces the value of the file with a default.
+/// This is the expected behavior when secrets are injected through the environment on top of a committed configuration file.
+///
+/// Nested fields are overridden surgically: `MYAPP_DATABASE_POOL_MAX=50` only changes `database.pool.max`, and keeps its siblings of the file.
+/// The variables are typed by the loaded file rather than by `Config::default()`, so this also works for sections that are `None` by default,
+/// as long as they are present in the file.
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler] and [FileHandler].
+/// * `config_directory` - The configuration directory, provided to [FileHandler].
+/// * `config_file_name` - The configuration file name, provided to [FileHandler].
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the `Config` of the file, overridden by the environment variables.
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_sparse<IntoString: Into<String>, Config>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+) -> Result<Config, ConfigLoadError>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+{
+    let app_name = app_name.into();
+
+    let env_handler = EnvHandler::<Config>::new(app_name.clone());
+    let file_handler = FileHandler::<Config>::new(
+        app_name,
+        config_directory.map(Into::into),
+        config_file_name.map(Into::into),
+    )?;
+
+    let file_config = file_handler.load_config()?;
+    let mut value = serde_json::to_value(file_config).map_err(FileConfigParseError::from)?;
+
+    let env_value = env_handler.load_sparse_with_schema(&value)?;
+    merger::merge_values(&mut value, env_value);
+    let config = serde_json::from_value(value).map_err(EnvironmentConfigParseError::from)?;
+
+    Ok(config)
+}
+
+/// Information about where [load_with_metadata] loaded the configuration from, e.g. for logging it at startup.
+///
+/// # Fields
+///
+/// * `env_prefix` - The prefix of the environment variables, without the trailing `_`.
+/// * `config_file_path` - The resolved path of the configuration file.
+/// * `created_config_file` - Whether the configuration file did not exist and was newly created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadMetadata {
+    pub env_prefix: String,
+    pub config_file_path: PathBuf,
+    pub created_config_file: bool,
+}
+
+/// Loads configurations from environment variables and a file, and merges them together, like [load].
+/// Additionally, this function returns a [LoadMetadata] describing where the configuration was loaded from.
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler] and [FileHandler].
+/// * `config_directory` - The configuration directory, provided to [FileHandler].
+/// * `config_file_name` - The configuration file name, provided to [FileHandler].
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the merged `FileConfig` and the [LoadMetadata].
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_with_metadata<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+) -> Result<(FileConfig, LoadMetadata), ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig>,
+    EnvConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    load_layers(
+        app_name,
+        config_directory,
+        config_file_name,
+        LoadHooks::default(),
+    )
+}
+
+/// Hooks to post-process each layer in [load_with_hooks] before they are merged, e.g. to normalize a path.
+///
+/// Both hooks default to the identity, so only the hooks that are needed have to be set.
+///
+/// # Type Parameters
+///
+/// * `FileConfig` - The configuration type loaded from the file.
+/// * `EnvConfig` - The configuration type loaded from the environment variables.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::LoadHooks;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// let hooks: LoadHooks<Config, Config> = LoadHooks::default().with_file_hook(|mut config: Config| {
+///     config.name = config.name.to_lowercase();
+///     config
+/// });
+/// ```
+pub struct LoadHooks<FileConfig, EnvConfig> {
+    env_hook: Box<dyn FnOnce(EnvConfig) -> EnvConfig>,
+    file_hook: Box<dyn FnOnce(FileConfig) -> FileConfig>,
+}
+
+impl<FileConfig, EnvConfig> LoadHooks<FileConfig, EnvConfig> {
+    /// Sets the hook applied to the `EnvConfig` loaded from the environment variables.
+    ///
+    /// # Parameters
+    ///
+    /// * `hook` - The function post-processing the `EnvConfig`.
+    ///
+    /// # Returns
+    ///
+    /// The `LoadHooks` instance with the hook set.
+    pub fn with_env_hook<Hook>(mut self, hook: Hook) -> Self
+    where
+        Hook: FnOnce(EnvConfig) -> EnvConfig + 'static,
+    {
+        self.env_hook = Box::new(hook);
+        self
+    }
+
+    /// Sets the hook applied to the `FileConfig` loaded from the file.
+    ///
+    /// # Parameters
+    ///
+    /// * `hook` - The function post-processing the `FileConfig`.
+    ///
+    /// # Returns
+    ///
+    /// The `LoadHooks` instance with the hook set.
+    pub fn with_file_hook<Hook>(mut self, hook: Hook) -> Self
+    where
+        Hook: FnOnce(FileConfig) -> FileConfig + 'static,
+    {
+        self.file_hook = Box::new(hook);
+        self
+    }
+}
+
+impl<FileConfig, EnvConfig> Default for LoadHooks<FileConfig, EnvConfig> {
+    fn default() -> Self {
+        LoadHooks {
+            env_hook: Box::new(|env_config| env_config),
+            file_hook: Box::new(|file_config| file_config),
+        }
+    }
+}
+
+impl<FileConfig, EnvConfig> fmt::Debug for LoadHooks<FileConfig, EnvConfig> {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("LoadHooks")
+    }
+}
+
+/// Loads configurations from environment variables and a file, and merges them together, like [load].
+/// Before merging, the hooks of [LoadHooks] are applied to the loaded layers, e.g. to normalize a path or lowercase a field.
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler] and [FileHandler].
+/// * `config_directory` - The configuration directory, provided to [FileHandler].
+/// * `config_file_name` - The configuration file name, provided to [FileHandler].
+/// * `hooks` - The hooks to apply to the layers.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the merged `FileConfig`.
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_with_hooks<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+    hooks: LoadHooks<FileConfig, EnvConfig>,
+) -> Result<FileConfig, ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig>,
+    EnvConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    let (merged_config, _) = load_layers(app_name, config_directory, config_file_name, hooks)?;
+
+    Ok(merged_config)
+}
+
+/// Loads and merges both layers for [load_with_metadata] and [load_with_hooks].
+fn load_layers<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+    hooks: LoadHooks<FileConfig, EnvConfig>,
+) -> Result<(FileConfig, LoadMetadata), ConfigLoadError>
 where
     FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig>,
     EnvConfig: Serialize + for<'de> Deserialize<'de>,
@@ -45,13 +346,242 @@ where
     let config_directory = config_directory.map(Into::into);
     let config_file_name = config_file_name.map(Into::into);
 
-    let env_handler = EnvHandler::new(app_name.clone());
-    let file_handler = FileHandler::new(app_name, config_directory, config_file_name)?;
+    let env_handler = EnvHandler::<EnvConfig>::new(app_name.clone());
+    let file_handler =
+        FileHandler::<FileConfig>::new(app_name, config_directory, config_file_name)?;
+    let created_config_file = !file_handler.config_file_path.exists();
+
+    let env_config = (hooks.env_hook)(env_handler.load_config()?);
+    let file_config = (hooks.file_hook)(file_handler.load_config()?);
+
+    let merged_config = merger::merge(file_config, env_config);
+    let metadata = LoadMetadata {
+        env_prefix: env_handler.resolved_prefix(),
+        config_file_path: file_handler.config_file_path,
+        created_config_file,
+    };
+
+    Ok((merged_config, metadata))
+}
+
+/// Defines which sources [load_with_options] reads from.
+///
+/// By default, both sources are used, which makes [load_with_options] behave like [load].
+///
+/// # Fields
+///
+/// * `use_env` - Whether to load the `EnvConfig` from environment variables. If disabled, `EnvConfig::default()` is used.
+/// * `use_file` - Whether to load the `FileConfig` from a file. If disabled, `FileConfig::default()` is used,
+///   and no [FileHandler] is constructed, so no configuration directory or file is created.
+/// * `allow_missing_file_layer` - Whether a configuration directory that can not be resolved or created (e.g. in a sandbox with a read-only root),
+///   or a configuration file that can not be loaded because of a read-only filesystem or missing permissions,
+///   should be logged as a warning, and `FileConfig::default()` be used instead of returning an error. Disabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    pub use_env: bool,
+    pub use_file: bool,
+    pub allow_missing_file_layer: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            use_env: true,
+            use_file: true,
+            allow_missing_file_layer: false,
+        }
+    }
+}
+
+/// Loads configurations from the sources enabled in `options`, and merges them together.
+/// This function works like [load], but allows skipping the environment variables (e.g. for desktop applications)
+/// or the file (e.g. for containers configured by environment only).
+///
+/// The function takes the same generic type parameters as [load], but additionally requires `FileConfig` and `EnvConfig` to implement `Default`.
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler] and [FileHandler].
+/// * `config_directory` - The configuration directory, provided to [FileHandler].
+/// * `config_file_name` - The configuration file name, provided to [FileHandler].
+/// * `options` - The sources to load from.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the merged `FileConfig`.
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_with_options<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+    options: LoadOptions,
+) -> Result<FileConfig, ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig> + Default,
+    EnvConfig: Serialize + for<'de> Deserialize<'de> + Default,
+{
+    let app_name = app_name.into();
+
+    let env_config = if options.use_env {
+        EnvHandler::<EnvConfig>::new(app_name.clone()).load_config()?
+    } else {
+        EnvConfig::default()
+    };
+
+    let file_config = if options.use_file {
+        let config_directory = config_directory.map(Into::into);
+        let config_file_name = config_file_name.map(Into::into);
+        let file_handler =
+            FileHandler::<FileConfig>::new(app_name, config_directory, config_file_name)
+                .map_err(ConfigLoadError::from)
+                .and_then(|file_handler| {
+                    file_handler
+                        .create_config_directory()
+                        .map_err(|error| FileConfigParseError::IO(error).into())
+                        .map(|_| file_handler)
+                });
+
+        match file_handler.map(|file_handler| file_handler.load_config()) {
+            Ok(Ok(file_config)) => file_config,
+            Ok(Err(
+                FileConfigParseError::IO(error)
+                | FileConfigParseError::Save(ConfigSaveError::IO(error)),
+            )) if options.allow_missing_file_layer && is_read_only_error(&error) => {
+                warn!(
+                    "Unable to use the config file, continuing without it: {}",
+                    error
+                );
+                FileConfig::default()
+            }
+            Ok(Err(error)) => return Err(error.into()),
+            Err(error) if options.allow_missing_file_layer => {
+                warn!(
+                    "Unable to use the config directory, continuing without the config file: {}",
+                    error
+                );
+                FileConfig::default()
+            }
+            Err(error) => return Err(error),
+        }
+    } else {
+        FileConfig::default()
+    };
+
+    let merged_config = merger::merge(file_config, env_config);
+
+    Ok(merged_config)
+}
+
+/// Loads configurations from environment variables and a [ConfigStore], and merges them together.
+/// This function works like [load], but reads the file layer from any store instead of constructing a [FileHandler].
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler].
+/// * `store` - The store to load the `FileConfig` from, e.g. a [FileHandler] or an [InMemoryHandler].
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the merged `FileConfig`.
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_from_store<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    store: &dyn ConfigStore<FileConfig>,
+) -> Result<FileConfig, ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig>,
+    EnvConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    let env_handler = EnvHandler::<EnvConfig>::new(app_name);
 
     let env_config = env_handler.load_config()?;
-    let file_config = file_handler.load_config()?;
+    let file_config = store.load()?;
 
-    let merged_config = merger::merge(env_config, file_config);
+    let merged_config = merger::merge(file_config, env_config);
 
     Ok(merged_config)
 }
+
+/// Defines which sources [load_lenient] may skip when they fail to load.
+///
+/// By default, no source is lenient, which makes [load_lenient] behave like [load].
+///
+/// # Fields
+///
+/// * `env` - Whether a failure to parse the environment variables should be logged and skipped.
+/// * `file` - Whether a failure to load the configuration file should be logged and skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Leniency {
+    pub env: bool,
+    pub file: bool,
+}
+
+/// Loads configurations from environment variables and a file, and merges them together, skipping sources that fail to load.
+/// This function works like [load], but a failing source that is marked as lenient in `leniency` is logged as a warning and skipped:
+/// * If the environment variables fail to parse, the file config is returned without merging.
+/// * If the file config fails to load, the environment config is merged into `FileConfig::default()`.
+///
+/// A failing source that is not marked as lenient is returned as an error, like in [load].
+/// If both sources fail, [ConfigLoadError::AllSources] is returned.
+///
+/// The function takes the same generic type parameters as [load], but additionally requires `FileConfig` to implement `Default`.
+///
+/// # Parameters
+///
+/// * `app_name` - The name of the application, provided to [EnvHandler] and [FileHandler].
+/// * `config_directory` - The configuration directory, provided to [FileHandler].
+/// * `config_file_name` - The configuration file name, provided to [FileHandler].
+/// * `leniency` - The sources that may be skipped when they fail to load.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the merged `FileConfig`.
+/// * Failure is indicated by an `Err` value, containing an instance of [ConfigLoadError].
+pub fn load_lenient<IntoString: Into<String>, FileConfig, EnvConfig>(
+    app_name: IntoString,
+    config_directory: Option<IntoString>,
+    config_file_name: Option<IntoString>,
+    leniency: Leniency,
+) -> Result<FileConfig, ConfigLoadError>
+where
+    FileConfig: Serialize + for<'de> Deserialize<'de> + MergeFrom<EnvConfig> + Default,
+    EnvConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    let app_name = app_name.into();
+    let config_directory = config_directory.map(Into::into);
+    let config_file_name = config_file_name.map(Into::into);
+
+    let env_handler = EnvHandler::<EnvConfig>::new(app_name.clone());
+    let env_result = match env_handler.load_config() {
+        Err(error) if leniency.env => {
+            warn!("Unable to parse environment config, skipping it: {}", error);
+            Err(error)
+        }
+        result => Ok(result?),
+    };
+
+    let file_result =
+        match FileHandler::<FileConfig>::new(app_name, config_directory, config_file_name)
+            .map_err(ConfigLoadError::from)
+            .and_then(|file_handler| file_handler.load_config().map_err(ConfigLoadError::from))
+        {
+            Err(error) if leniency.file => {
+                warn!("Unable to load file config, skipping it: {}", error);
+                Err(error)
+            }
+            result => Ok(result?),
+        };
+
+    match (env_result, file_result) {
+        (Ok(env_config), Ok(file_config)) => Ok(merger::merge(file_config, env_config)),
+        (Ok(env_config), Err(_)) => Ok(merger::merge(FileConfig::default(), env_config)),
+        (Err(_), Ok(file_config)) => Ok(file_config),
+        (Err(env), Err(file)) => Err(ConfigLoadError::AllSources {
+            env,
+            file: Box::new(file),
+        }),
+    }
+}
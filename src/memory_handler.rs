@@ -0,0 +1,226 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    marker::PhantomData,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use lum_libs::serde::{Deserialize, Serialize};
+
+use crate::{Codec, ConfigSaveError, ConfigStore, FileConfigParseError, JsonCodec};
+
+/// A handler for loading and saving configuration from/to an in-memory document.
+///
+/// The `InMemoryHandler` struct mirrors the load/save behavior of [crate::FileHandler], but never touches the filesystem.
+/// This makes it a drop-in fake for tests, which would otherwise need temporary directories.
+///
+/// The document is stored in a shared buffer. Clones of an `InMemoryHandler` share the same buffer.
+///
+/// # Type Parameters
+///
+/// * `Config` - The configuration type that implements `Serialize` and `Deserialize`. This is the type to which the document will be deserialized.
+/// * `DocumentCodec` - The [Codec] used to read and write the document. Defaults to [JsonCodec].
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::memory_handler::InMemoryHandler;
+///
+/// #[derive(Serialize, Deserialize)]
+/// #[serde(default)]
+/// struct Config {
+///     key: String,
+/// }
+///
+/// impl Default for Config {
+///     fn default() -> Self {
+///         Config {
+///             key: "default_value".to_string(),
+///         }
+///     }
+/// }
+///
+/// let handler: InMemoryHandler<Config> = InMemoryHandler::new();
+/// let config = handler.load_config().unwrap();
+///
+/// assert_eq!(config.key, "default_value");
+/// assert!(handler.document().unwrap().contains("default_value"));
+/// ```
+#[derive(Debug)]
+pub struct InMemoryHandler<Config, DocumentCodec = JsonCodec>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    DocumentCodec: Codec,
+{
+    document: Arc<Mutex<Option<String>>>,
+    codec: DocumentCodec,
+    _phantom_document: PhantomData<Config>,
+}
+
+impl<Config> InMemoryHandler<Config>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+{
+    /// Creates a new `InMemoryHandler` without a document.
+    ///
+    /// # Returns
+    ///
+    /// A new `InMemoryHandler` instance.
+    pub fn new() -> Self {
+        InMemoryHandler {
+            document: Arc::new(Mutex::new(None)),
+            codec: JsonCodec::default(),
+            _phantom_document: PhantomData,
+        }
+    }
+}
+
+impl<Config> Default for InMemoryHandler<Config>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+{
+    fn default() -> Self {
+        InMemoryHandler::new()
+    }
+}
+
+impl<Config, DocumentCodec> Clone for InMemoryHandler<Config, DocumentCodec>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    DocumentCodec: Codec + Clone,
+{
+    fn clone(&self) -> Self {
+        InMemoryHandler {
+            document: Arc::clone(&self.document),
+            codec: self.codec.clone(),
+            _phantom_document: PhantomData,
+        }
+    }
+}
+
+impl<Config, DocumentCodec> InMemoryHandler<Config, DocumentCodec>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    DocumentCodec: Codec,
+{
+    /// Replaces the [Codec] used to read and write the document.
+    ///
+    /// # Arguments
+    ///
+    /// * `codec` - The codec to use from now on.
+    ///
+    /// # Returns
+    ///
+    /// A new `InMemoryHandler` instance using the given codec and the same document buffer.
+    pub fn with_codec<NewCodec: Codec>(self, codec: NewCodec) -> InMemoryHandler<Config, NewCodec> {
+        InMemoryHandler {
+            document: self.document,
+            codec,
+            _phantom_document: PhantomData,
+        }
+    }
+
+    /// Sets the document, as if a configuration file with the given content existed.
+    ///
+    /// # Arguments
+    ///
+    /// * `document` - The serialized configuration.
+    ///
+    /// # Returns
+    ///
+    /// The `InMemoryHandler` instance with the document set.
+    pub fn with_document<IntoString: Into<String>>(self, document: IntoString) -> Self {
+        *self.lock_document() = Some(document.into());
+        self
+    }
+
+    /// Returns a copy of the current document, or `None` if no document was saved yet.
+    pub fn document(&self) -> Option<String> {
+        self.lock_document().clone()
+    }
+
+    /// Returns the shared buffer holding the document.
+    pub fn shared_document(&self) -> Arc<Mutex<Option<String>>> {
+        Arc::clone(&self.document)
+    }
+
+    /// Saves the configuration to the document, overwriting any previous document.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration to be saved.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the unit type `()`.
+    /// * Failure is indicated by an `Err` value, containing a `ConfigSaveError`.
+    pub fn save_config(&self, config: &Config) -> Result<(), ConfigSaveError> {
+        let config_string = self
+            .codec
+            .to_string(config)
+            .map_err(|error| ConfigSaveError::Codec(Box::new(error)))?;
+        *self.lock_document() = Some(config_string);
+
+        Ok(())
+    }
+
+    /// Loads the configuration from the document.
+    ///
+    /// If there is no document yet, an empty document is used.
+    /// Like [crate::FileHandler::load_config], the configuration is saved again afterwards,
+    /// so missing fields are backfilled with the defaults serde used for them.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the Config instance.
+    /// * Failure is indicated by an `Err` value, containing a `FileConfigParseError`.
+    pub fn load_config(&self) -> Result<Config, FileConfigParseError> {
+        let document = match self.document() {
+            Some(document) => document,
+            None => self
+                .codec
+                .to_string(&BTreeMap::<String, String>::new())
+                .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?,
+        };
+
+        let config = self
+            .codec
+            .from_str(&document)
+            .map_err(|error| FileConfigParseError::Codec(Box::new(error)))?;
+        self.save_config(&config)?;
+
+        Ok(config)
+    }
+
+    fn lock_document(&self) -> MutexGuard<'_, Option<String>> {
+        self.document
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<Config, DocumentCodec> ConfigStore<Config> for InMemoryHandler<Config, DocumentCodec>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    DocumentCodec: Codec,
+{
+    fn load(&self) -> Result<Config, FileConfigParseError> {
+        self.load_config()
+    }
+
+    fn save(&self, config: &Config) -> Result<(), ConfigSaveError> {
+        self.save_config(config)
+    }
+
+    fn exists(&self) -> Result<bool, io::Error> {
+        Ok(self.lock_document().is_some())
+    }
+
+    fn delete(&self) -> Result<(), io::Error> {
+        *self.lock_document() = None;
+        Ok(())
+    }
+}
@@ -1,4 +1,12 @@
-use lum_libs::serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap},
+    hash::{BuildHasher, Hash},
+};
+
+use lum_libs::{
+    serde::{Deserialize, Serialize},
+    serde_json::{self, Value},
+};
 
 /// A trait that defines a method for merging an instance of T into an instance of Self.
 pub trait MergeFrom<T> {
@@ -84,6 +92,121 @@ where
     }
 }
 
+/// A trait that provides merging as a fluent extension method, which reads well when chaining multiple layers.
+///
+/// It is implemented for every type that implements `MergeFrom<PartialConfig>`.
+/// If the compiler can not infer your config types, use the [merge] function instead.
+///
+/// # Examples
+///
+/// ```
+/// use lum_config::{MergeExt, MergeFrom};
+///
+/// struct Config {
+///     name: String,
+///     port: u16,
+/// }
+///
+/// struct NameOverride(String);
+/// struct PortOverride(u16);
+///
+/// impl MergeFrom<NameOverride> for Config {
+///     fn merge_from(self, other: NameOverride) -> Self {
+///         Config { name: other.0, ..self }
+///     }
+/// }
+///
+/// impl MergeFrom<PortOverride> for Config {
+///     fn merge_from(self, other: PortOverride) -> Self {
+///         Config { port: other.0, ..self }
+///     }
+/// }
+///
+/// let config = Config { name: "default".to_string(), port: 80 }
+///     .merge_with(NameOverride("file".to_string()))
+///     .merge_with(PortOverride(8080));
+///
+/// assert_eq!(config.name, "file");
+/// assert_eq!(config.port, 8080);
+/// ```
+pub trait MergeExt<PartialConfig> {
+    /// Merges an instance of PartialConfig into an instance of Self.
+    ///
+    /// # Returns
+    ///
+    /// An instance of Self, which is the result of the merge operation.
+    fn merge_with(self, partial_config: PartialConfig) -> Self;
+}
+
+/// When a type implements `MergeFrom` for another type, automatically implement `MergeExt` on the same type for the other type.
+impl<Config, PartialConfig> MergeExt<PartialConfig> for Config
+where
+    Config: MergeFrom<PartialConfig>,
+{
+    fn merge_with(self, partial_config: PartialConfig) -> Self {
+        self.merge_from(partial_config)
+    }
+}
+
+/// A trait that provides fallible merging as a fluent extension method, which reads well when chaining multiple layers.
+///
+/// It is implemented for every type that implements `TryMergeFrom<PartialConfig>`, and therefore also for every type that implements `MergeFrom<PartialConfig>`.
+/// If the compiler can not infer your config types, use the [try_merge] function instead.
+///
+/// # Examples
+///
+/// ```
+/// use lum_config::{TryMergeExt, TryMergeFrom};
+///
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// struct PortOverride(i64);
+///
+/// impl TryMergeFrom<PortOverride> for Config {
+///     type Error = String;
+///
+///     fn try_merge_from(self, other: PortOverride) -> Result<Self, Self::Error> {
+///         let port = u16::try_from(other.0).map_err(|_| format!("invalid port {}", other.0))?;
+///         Ok(Config { port })
+///     }
+/// }
+///
+/// let config = Config { port: 80 }
+///     .try_merge_with(PortOverride(8080))
+///     .and_then(|config| config.try_merge_with(PortOverride(8081)))
+///     .unwrap();
+/// assert_eq!(config.port, 8081);
+///
+/// let result = Config { port: 80 }.try_merge_with(PortOverride(-1));
+/// assert_eq!(result.err().unwrap(), "invalid port -1");
+/// ```
+pub trait TryMergeExt<PartialConfig>: Sized {
+    type Error;
+
+    /// Tries to merge an instance of PartialConfig into an instance of Self.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing an instance of Self, which is the result of the merge operation.
+    /// * Failure is indicated by an `Err` value, containing an instance of the error type.
+    fn try_merge_with(self, partial_config: PartialConfig) -> Result<Self, Self::Error>;
+}
+
+/// When a type implements `TryMergeFrom` for another type, automatically implement `TryMergeExt` on the same type for the other type.
+impl<Config, PartialConfig> TryMergeExt<PartialConfig> for Config
+where
+    Config: TryMergeFrom<PartialConfig>,
+{
+    type Error = Config::Error;
+
+    fn try_merge_with(self, partial_config: PartialConfig) -> Result<Self, Self::Error> {
+        self.try_merge_from(partial_config)
+    }
+}
+
 /// Merges two instances of type `Config` and `PartialConfig` into a single instance of `Config`.
 ///
 /// By using this function instead of calling `merge_from` directly, you can help the Rust compiler to better infer your config types.
@@ -111,6 +234,54 @@ where
     config.merge_from(partial_config)
 }
 
+/// Merges clones of `config` and `partial_config` like [merge], leaving the originals untouched.
+///
+/// As [MergeFrom] consumes both of its arguments, this clones both of them first, so `Config` and `PartialConfig` must implement `Clone`.
+/// Keep the cost of cloning in mind for large configurations. This is useful to keep the base configuration around, e.g. for diffing or rollback.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `partial_config` into.
+/// * `partial_config` - The instance of `PartialConfig` to be merged into `config`.
+///
+/// # Returns
+///
+/// A new instance of `Config`, which is the result of the merge operation.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::merger::{merge_cloned, MergeFrom};
+///
+/// #[derive(Clone, Serialize, Deserialize)]
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// impl MergeFrom<Config> for Config {
+///     fn merge_from(self, other: Config) -> Self {
+///         other
+///     }
+/// }
+///
+/// let base = Config { port: 80 };
+/// let merged = merge_cloned(&base, &Config { port: 8080 });
+///
+/// assert_eq!(base.port, 80);
+/// assert_eq!(merged.port, 8080);
+/// ```
+pub fn merge_cloned<Config, PartialConfig>(
+    config: &Config,
+    partial_config: &PartialConfig,
+) -> Config
+where
+    Config: Serialize + for<'de> Deserialize<'de> + MergeFrom<PartialConfig> + Clone,
+    PartialConfig: Serialize + for<'de> Deserialize<'de> + Clone,
+{
+    merge(config.clone(), partial_config.clone())
+}
+
 /// Tries to merge two instances of type `Config` and `PartialConfig` into a single instance of `Config`.
 ///
 /// By using this function instead of calling `try_merge_from` directly, you can help the Rust compiler to better infer your config types.
@@ -142,3 +313,752 @@ where
 {
     config.try_merge_from(partial_config)
 }
+
+/// Tries to merge `partial_config` into `config` like [try_merge], but keeps `config` if the merge fails.
+///
+/// As [TryMergeFrom] consumes `config`, a clone of it is merged, so `Config` must implement `Clone`.
+/// This is useful for a reload loop, which should log a failed merge and continue with the last good configuration.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `partial_config` into.
+/// * `partial_config` - The instance of `PartialConfig` to be merged into `config`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing an instance of `Config`, which is the result of the merge operation.
+/// * Failure is indicated by an `Err` value, containing a tuple of the untouched `config` and the error of the merge operation.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::merger::{try_merge_keep_base, TryMergeFrom};
+///
+/// #[derive(Debug, Clone, Serialize, Deserialize)]
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// impl TryMergeFrom<Config> for Config {
+///     type Error = String;
+///
+///     fn try_merge_from(self, other: Config) -> Result<Self, Self::Error> {
+///         match other.port {
+///             0 => Err("port must not be 0".to_string()),
+///             _ => Ok(other),
+///         }
+///     }
+/// }
+///
+/// let (config, error) = try_merge_keep_base(Config { port: 80 }, Config { port: 0 }).unwrap_err();
+///
+/// assert_eq!(config.port, 80);
+/// assert_eq!(error, "port must not be 0");
+/// ```
+pub fn try_merge_keep_base<Config, PartialConfig>(
+    config: Config,
+    partial_config: PartialConfig,
+) -> Result<Config, (Config, Config::Error)>
+where
+    Config: Serialize + for<'de> Deserialize<'de> + TryMergeFrom<PartialConfig> + Clone,
+    PartialConfig: Serialize + for<'de> Deserialize<'de>,
+{
+    match config.clone().try_merge_from(partial_config) {
+        Ok(merged_config) => Ok(merged_config),
+        Err(error) => Err((config, error)),
+    }
+}
+
+/// Tries to merge two instances of type `Config` and `PartialConfig` into a single instance of `Config`, and validates the result.
+///
+/// This works like [try_merge], but additionally runs `validate` on the merged config as part of the merge.
+/// This keeps "merge and check" atomic, e.g. to reject configurations in which mutually exclusive fields are both set.
+/// The validation error flows through the `Error` type of `TryMergeFrom`, just like a merge error.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `partial_config` into.
+/// * `partial_config` - The instance of `PartialConfig` to be merged into `config`.
+/// * `validate` - A closure validating the merged config.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing an instance of `Config`, which is the result of the merge operation.
+/// * Failure is indicated by an `Err` value, containing an instance of the error type, returned either by the merge or by `validate`.
+pub fn try_merge_validated<Config, PartialConfig, Validator>(
+    config: Config,
+    partial_config: PartialConfig,
+    validate: Validator,
+) -> Result<Config, Config::Error>
+where
+    Config: Serialize + for<'de> Deserialize<'de> + TryMergeFrom<PartialConfig>,
+    PartialConfig: Serialize + for<'de> Deserialize<'de>,
+    Validator: FnOnce(&Config) -> Result<(), Config::Error>,
+{
+    let merged_config = config.try_merge_from(partial_config)?;
+    validate(&merged_config)?;
+
+    Ok(merged_config)
+}
+
+/// Deep-merges `patch` into `base`.
+///
+/// Objects are merged key by key, recursively. Every other value in `patch`, including arrays and `null`,
+/// replaces the corresponding value in `base`. Keys of `base` that are not present in `patch` are left untouched.
+///
+/// # Parameters
+///
+/// * `base` - The value to merge `patch` into.
+/// * `patch` - The value to be merged into `base`.
+pub fn merge_values(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, patch_value) in patch {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_values(base_value, patch_value),
+                    None => {
+                        base.insert(key, patch_value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// Converts a configuration into a structurally compatible type, e.g. an `EnvConfig` into a `FileConfig`, without merging it into anything.
+///
+/// `source` is serialized to a `serde_json::Value`, which is then deserialized into `Target`.
+/// Fields of `source` that `Target` does not have are silently dropped.
+/// Fields of `Target` that `source` does not have need a default (e.g. `#[serde(default)]`), otherwise the conversion fails.
+///
+/// # Parameters
+///
+/// * `source` - The configuration to be converted.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the converted `Target`.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`, if `source` can not be serialized, or `Target` can not be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::merger::convert;
+///
+/// #[derive(Serialize)]
+/// struct EnvConfig {
+///     token: String,
+///     debug: bool,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct FileConfig {
+///     token: String,
+///     #[serde(default)]
+///     port: u16,
+/// }
+///
+/// let env_config = EnvConfig { token: "secret".to_string(), debug: true };
+/// let file_config: FileConfig = convert(env_config).unwrap();
+///
+/// assert_eq!(file_config.token, "secret");
+/// assert_eq!(file_config.port, 0);
+/// ```
+pub fn convert<Source, Target>(source: Source) -> Result<Target, serde_json::Error>
+where
+    Source: Serialize,
+    Target: for<'de> Deserialize<'de>,
+{
+    serde_json::from_value(serde_json::to_value(source)?)
+}
+
+/// Merges two instances of the same type, where the fields of `patch` that differ from the default override the ones of `config`.
+///
+/// This is useful to layer configurations of the same type, e.g. a user configuration over a system configuration.
+/// All three are serialized to `serde_json::Value`s. Every value of `patch` that equals the value of `Config::default()` at the same path is dropped,
+/// and the rest is merged like in [merge_values]: objects are merged key by key, recursively, and every other value replaces the one of `config`.
+///
+/// Note that a field of `patch` that was explicitly set to its default value can not be told apart from a field that was not set,
+/// so it never overrides `config`. Use `Option` fields with `None` defaults if this distinction matters.
+/// Conversely, if a nested object of `patch` has no counterpart in the default (e.g. it is `None` there), it is merged as a whole, including its default fields.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `patch` into.
+/// * `patch` - The instance of `Config` to be merged into `config`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing an instance of `Config`, which is the result of the merge operation.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`, if any instance can not be serialized, or the result can not be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::merger::merge_same;
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// struct Config {
+///     theme: String,
+///     port: u16,
+/// }
+///
+/// let system = Config { theme: "dark".to_string(), port: 80 };
+/// let user = Config { theme: "light".to_string(), ..Config::default() };
+///
+/// let merged = merge_same(system, user).unwrap();
+/// assert_eq!(merged.theme, "light");
+/// assert_eq!(merged.port, 80);
+/// ```
+pub fn merge_same<Config>(config: Config, patch: Config) -> Result<Config, serde_json::Error>
+where
+    Config: Serialize + for<'de> Deserialize<'de> + Default,
+{
+    let mut value = serde_json::to_value(config)?;
+    let mut patch = serde_json::to_value(patch)?;
+    let default = serde_json::to_value(Config::default())?;
+
+    if remove_defaults(&mut patch, &default) {
+        return serde_json::from_value(value);
+    }
+    merge_values(&mut value, patch);
+
+    serde_json::from_value(value)
+}
+
+/// Removes all values of `value` that equal the value of `default` at the same path, descending into objects present in both.
+///
+/// Returns whether `value` equals `default` as a whole, in which case it should be removed by the caller.
+fn remove_defaults(value: &mut Value, default: &Value) -> bool {
+    let (Value::Object(object), Value::Object(default)) = (&mut *value, default) else {
+        return value == default;
+    };
+
+    object.retain(|key, nested| match default.get(key) {
+        Some(default) => !remove_defaults(nested, default),
+        None => true,
+    });
+
+    object.is_empty()
+}
+
+/// Merges a partial configuration whose fields are all `Option`s into `config`, where `Some` overrides and `None` keeps the value of `config`.
+///
+/// This covers the common pattern of a `Partial` type mirroring `Config` with every field wrapped in `Option`, without implementing [MergeFrom] by hand.
+/// The fields are matched by their serialized names, so `Partial` has to use the same names (and `serde` attributes) as `Config`.
+/// `patch` is serialized to a `serde_json::Value`, every `null` is removed from it, and the rest is merged like in [merge_values].
+/// Nested partial structs are merged recursively the same way, while `Some` of any other value replaces the value of `config` as a whole.
+///
+/// As `None` is dropped, it can not be used to reset a field. Use [deep_merge_with_deletion] for that.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `patch` into.
+/// * `patch` - The partial configuration to be merged into `config`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing an instance of `Config`, which is the result of the merge operation.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`, if either can not be serialized, or the result can not be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::merger::merge_optionals;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct PartialConfig {
+///     host: Option<String>,
+///     port: Option<u16>,
+/// }
+///
+/// let config = Config { host: "localhost".to_string(), port: 80 };
+/// let patch = PartialConfig { host: None, port: Some(8080) };
+///
+/// let merged = merge_optionals(config, patch).unwrap();
+/// assert_eq!(merged.host, "localhost");
+/// assert_eq!(merged.port, 8080);
+/// ```
+pub fn merge_optionals<Config, Partial>(
+    config: Config,
+    patch: Partial,
+) -> Result<Config, serde_json::Error>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    Partial: Serialize,
+{
+    let mut value = serde_json::to_value(config)?;
+    let mut patch = serde_json::to_value(patch)?;
+    if remove_nulls(&mut patch) {
+        return serde_json::from_value(value);
+    }
+    merge_values(&mut value, patch);
+
+    serde_json::from_value(value)
+}
+
+/// Removes all `null` values of `value`, descending into objects.
+///
+/// Returns whether `value` is `null` itself, in which case it should be removed by the caller.
+fn remove_nulls(value: &mut Value) -> bool {
+    if let Value::Object(object) = value {
+        object.retain(|_, nested| !remove_nulls(nested));
+    }
+
+    value.is_null()
+}
+
+/// Deep-merges `patch` into `config` like [merge_values], but skips the merge if it would not change anything.
+///
+/// Whether the merge changes anything is checked on the serialized values, without allocating a merged document.
+/// If nothing changes, `config` is returned as it is, so e.g. a reload loop can skip writing or notifying listeners.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `patch` into.
+/// * `patch` - The value to be merged into `config`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing the instance of `Config`, and whether the merge changed it.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`, if either can not be serialized, or the result can not be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::{serde::{Deserialize, Serialize}, serde_json::json};
+/// use lum_config::merger::merge_if_changed;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// let (config, changed) = merge_if_changed(Config { port: 80 }, json!({ "port": 80 })).unwrap();
+/// assert!(!changed);
+///
+/// let (config, changed) = merge_if_changed(config, json!({ "port": 8080 })).unwrap();
+/// assert!(changed);
+/// assert_eq!(config.port, 8080);
+/// ```
+pub fn merge_if_changed<Config, Patch>(
+    config: Config,
+    patch: Patch,
+) -> Result<(Config, bool), serde_json::Error>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    Patch: Serialize,
+{
+    let mut value = serde_json::to_value(&config)?;
+    let patch = serde_json::to_value(patch)?;
+    if !changes_value(&value, &patch) {
+        return Ok((config, false));
+    }
+
+    merge_values(&mut value, patch);
+    let config = serde_json::from_value(value)?;
+
+    Ok((config, true))
+}
+
+/// Returns whether merging `patch` into `base` with [merge_values] would change `base`.
+fn changes_value(base: &Value, patch: &Value) -> bool {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            patch.iter().any(|(key, patch_value)| match base.get(key) {
+                Some(base_value) => changes_value(base_value, patch_value),
+                None => true,
+            })
+        }
+        (base, patch) => base != patch,
+    }
+}
+
+/// A value that is present in both the base and the patch of [merge_with_resolver].
+///
+/// # Fields
+///
+/// * `path` - The keys leading to the value, from the root of the document.
+/// * `base` - The value in the base.
+/// * `patch` - The value in the patch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub path: Vec<String>,
+    pub base: Value,
+    pub patch: Value,
+}
+
+/// Merges `patch` into `config`, letting `resolver` decide the value of every leaf that is present in both.
+///
+/// Both are serialized to `serde_json::Value`s and merged like in [merge_values]: objects are merged key by key, recursively,
+/// and keys that are only present in one of them are kept. Every other value present in both (a leaf, including arrays and `null`,
+/// or an object conflicting with a non-object) is passed to `resolver` as a [Conflict], and replaced by the returned value.
+/// The result is deserialized into `Config`.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `patch` into.
+/// * `patch` - The value to be merged into `config`.
+/// * `resolver` - A closure returning the merged value of a conflict.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing an instance of `Config`, which is the result of the merge operation.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`, if either can not be serialized, or the result can not be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::{serde::{Deserialize, Serialize}, serde_json::Value};
+/// use lum_config::merger::merge_with_resolver;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Limits {
+///     connections: u32,
+///     name: String,
+/// }
+///
+/// let base = Limits { connections: 100, name: "base".to_string() };
+/// let patch = Limits { connections: 50, name: "patch".to_string() };
+///
+/// let merged: Limits = merge_with_resolver(base, patch, |conflict| {
+///     match (conflict.base.as_u64(), conflict.patch.as_u64()) {
+///         (Some(base), Some(patch)) => Value::from(base.max(patch)),
+///         _ => conflict.patch,
+///     }
+/// })
+/// .unwrap();
+///
+/// assert_eq!(merged.connections, 100);
+/// assert_eq!(merged.name, "patch");
+/// ```
+pub fn merge_with_resolver<Config, Patch, Resolver>(
+    config: Config,
+    patch: Patch,
+    mut resolver: Resolver,
+) -> Result<Config, serde_json::Error>
+where
+    Config: Serialize + for<'de> Deserialize<'de>,
+    Patch: Serialize,
+    Resolver: FnMut(Conflict) -> Value,
+{
+    let mut value = serde_json::to_value(config)?;
+    let patch = serde_json::to_value(patch)?;
+    resolve_values(&mut value, patch, &mut Vec::new(), &mut resolver);
+
+    serde_json::from_value(value)
+}
+
+/// Deep-merges `patch` into `base` like [merge_values], calling `resolver` for every leaf present in both.
+fn resolve_values<Resolver>(
+    base: &mut Value,
+    patch: Value,
+    path: &mut Vec<String>,
+    resolver: &mut Resolver,
+) where
+    Resolver: FnMut(Conflict) -> Value,
+{
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, patch_value) in patch {
+                match base.get_mut(&key) {
+                    Some(base_value) => {
+                        path.push(key);
+                        resolve_values(base_value, patch_value, path, resolver);
+                        path.pop();
+                    }
+                    None => {
+                        base.insert(key, patch_value);
+                    }
+                }
+            }
+        }
+        (base, patch) => {
+            *base = resolver(Conflict {
+                path: path.clone(),
+                base: base.take(),
+                patch,
+            });
+        }
+    }
+}
+
+/// Deep-merges `patch` into `config` like [merge_values], but treats an explicit `null` in `patch` as a deletion.
+///
+/// Keys that are absent in `patch` leave `config` untouched, while keys that are explicitly `null` reset the value of `config`
+/// to the value of `Config::default()` at the same path. If the default has no value at that path, e.g. because the key is an entry of a map,
+/// the key is removed from `config` instead. Note that this means `null` can not be used to set an `Option` field to `None`
+/// if its default is `Some`, the field is reset to its default instead.
+///
+/// # Parameters
+///
+/// * `config` - The instance of `Config` to merge `patch` into.
+/// * `patch` - The value to be merged into `config`.
+///
+/// # Returns
+///
+/// A `Result` indicating success or failure.
+/// * Success is indicated by an `Ok` value, containing an instance of `Config`, which is the result of the merge operation.
+/// * Failure is indicated by an `Err` value, containing a `serde_json::Error`, if any value can not be serialized, or the result can not be deserialized.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use lum_libs::{serde::{Deserialize, Serialize}, serde_json::json};
+/// use lum_config::merger::deep_merge_with_deletion;
+///
+/// #[derive(Serialize, Deserialize)]
+/// #[serde(default)]
+/// struct Config {
+///     port: u16,
+///     name: String,
+///     labels: BTreeMap<String, String>,
+/// }
+///
+/// impl Default for Config {
+///     fn default() -> Self {
+///         Config { port: 80, name: "default".to_string(), labels: BTreeMap::new() }
+///     }
+/// }
+///
+/// let config = Config {
+///     port: 8080,
+///     name: "custom".to_string(),
+///     labels: BTreeMap::from([("team".to_string(), "core".to_string())]),
+/// };
+///
+/// let merged = deep_merge_with_deletion(config, json!({ "port": null, "labels": { "team": null } })).unwrap();
+/// assert_eq!(merged.port, 80);
+/// assert_eq!(merged.name, "custom");
+/// assert!(merged.labels.is_empty());
+/// ```
+pub fn deep_merge_with_deletion<Config, Patch>(
+    config: Config,
+    patch: Patch,
+) -> Result<Config, serde_json::Error>
+where
+    Config: Serialize + for<'de> Deserialize<'de> + Default,
+    Patch: Serialize,
+{
+    let mut value = serde_json::to_value(config)?;
+    let patch = serde_json::to_value(patch)?;
+    let default = serde_json::to_value(Config::default())?;
+    merge_values_with_deletion(&mut value, patch, Some(&default));
+
+    serde_json::from_value(value)
+}
+
+/// Deep-merges `patch` into `base` like [merge_values], resetting every key that is `null` in `patch` to its value in `default`,
+/// or removing it if `default` has no value for it.
+fn merge_values_with_deletion(base: &mut Value, patch: Value, default: Option<&Value>) {
+    match (base, patch) {
+        (base, Value::Null) => *base = default.cloned().unwrap_or(Value::Null),
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, patch_value) in patch {
+                let default = default.and_then(|default| default.get(&key));
+                match (base.get_mut(&key), patch_value) {
+                    (Some(_), Value::Null) if default.is_none() => {
+                        base.remove(&key);
+                    }
+                    (None, Value::Null) => {
+                        if let Some(default) = default {
+                            base.insert(key, default.clone());
+                        }
+                    }
+                    (Some(base_value), patch_value) => {
+                        merge_values_with_deletion(base_value, patch_value, default)
+                    }
+                    (None, patch_value) => {
+                        base.insert(key, patch_value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// A merge strategy for [merge_hash_map], [merge_btree_map] and [merge_vec_by_key], which keeps the value of the patch.
+///
+/// To merge the values recursively instead, pass [merge] as the strategy, which requires the values to implement `MergeFrom` for themselves.
+pub fn replace_value<T>(_base: T, patch: T) -> T {
+    patch
+}
+
+/// Merges `patch` into `base` key by key.
+///
+/// Keys that are only present in one of the maps are kept. For keys present in both, `strategy` is called with the value of `base`
+/// and the value of `patch`, and returns the merged value. Use [replace_value] to let the patch win, or [merge] to merge the values recursively.
+///
+/// # Parameters
+///
+/// * `base` - The map to merge `patch` into.
+/// * `patch` - The map to be merged into `base`.
+/// * `strategy` - A closure merging the values of keys present in both maps.
+///
+/// # Returns
+///
+/// The merged map.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use lum_config::merger::{merge_hash_map, replace_value};
+///
+/// let base = HashMap::from([("a", 1), ("b", 2)]);
+/// let patch = HashMap::from([("b", 20), ("c", 30)]);
+///
+/// let merged = merge_hash_map(base, patch, replace_value);
+/// assert_eq!(merged, HashMap::from([("a", 1), ("b", 20), ("c", 30)]));
+/// ```
+pub fn merge_hash_map<Key, MapValue, Hasher, Strategy>(
+    mut base: HashMap<Key, MapValue, Hasher>,
+    patch: HashMap<Key, MapValue, Hasher>,
+    mut strategy: Strategy,
+) -> HashMap<Key, MapValue, Hasher>
+where
+    Key: Eq + Hash,
+    Hasher: BuildHasher,
+    Strategy: FnMut(MapValue, MapValue) -> MapValue,
+{
+    for (key, patch_value) in patch {
+        let value = match base.remove(&key) {
+            Some(base_value) => strategy(base_value, patch_value),
+            None => patch_value,
+        };
+        base.insert(key, value);
+    }
+
+    base
+}
+
+/// Merges `patch` into `base` key by key, like [merge_hash_map].
+///
+/// # Parameters
+///
+/// * `base` - The map to merge `patch` into.
+/// * `patch` - The map to be merged into `base`.
+/// * `strategy` - A closure merging the values of keys present in both maps.
+///
+/// # Returns
+///
+/// The merged map.
+pub fn merge_btree_map<Key, MapValue, Strategy>(
+    mut base: BTreeMap<Key, MapValue>,
+    patch: BTreeMap<Key, MapValue>,
+    mut strategy: Strategy,
+) -> BTreeMap<Key, MapValue>
+where
+    Key: Ord,
+    Strategy: FnMut(MapValue, MapValue) -> MapValue,
+{
+    for (key, patch_value) in patch {
+        let value = match base.remove(&key) {
+            Some(base_value) => strategy(base_value, patch_value),
+            None => patch_value,
+        };
+        base.insert(key, value);
+    }
+
+    base
+}
+
+/// Merges `patch` into `base` element by element, matching the elements by the key returned by `key_fn`, e.g. servers by their name.
+///
+/// Unlike replacing or appending the whole sequence, this allows overriding a single element of a list of named elements.
+/// Elements of `base` keep their order. For every element of `patch` whose key matches an element of `base`,
+/// `strategy` is called with both elements, and the merged element takes the place of the element of `base`.
+/// The other elements of `patch` are appended in their order.
+/// If several elements of `base` share a key, only the first one is matched. Elements of `patch` sharing a key are merged one after another.
+///
+/// # Parameters
+///
+/// * `base` - The elements to merge `patch` into.
+/// * `patch` - The elements to be merged into `base`.
+/// * `key_fn` - A closure returning the key identifying an element.
+/// * `strategy` - A closure merging two elements with the same key. Use [replace_value] to let the patch win, or [merge] to merge them recursively.
+///
+/// # Returns
+///
+/// The merged elements.
+///
+/// # Examples
+///
+/// ```
+/// use lum_config::merger::{merge_vec_by_key, replace_value};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Server {
+///     name: &'static str,
+///     port: u16,
+/// }
+///
+/// let base = vec![Server { name: "a", port: 80 }, Server { name: "b", port: 81 }];
+/// let patch = vec![Server { name: "c", port: 90 }, Server { name: "a", port: 8080 }];
+///
+/// let merged = merge_vec_by_key(base, patch, |server| server.name, replace_value);
+/// assert_eq!(
+///     merged,
+///     vec![
+///         Server { name: "a", port: 8080 },
+///         Server { name: "b", port: 81 },
+///         Server { name: "c", port: 90 },
+///     ]
+/// );
+/// ```
+pub fn merge_vec_by_key<Element, Key, KeyFn, Strategy>(
+    base: Vec<Element>,
+    patch: Vec<Element>,
+    mut key_fn: KeyFn,
+    mut strategy: Strategy,
+) -> Vec<Element>
+where
+    Key: Eq + Hash,
+    KeyFn: FnMut(&Element) -> Key,
+    Strategy: FnMut(Element, Element) -> Element,
+{
+    let mut indices = HashMap::new();
+    let mut elements = Vec::with_capacity(base.len() + patch.len());
+    for element in base {
+        indices.entry(key_fn(&element)).or_insert(elements.len());
+        elements.push(Some(element));
+    }
+
+    for patch_element in patch {
+        match indices.entry(key_fn(&patch_element)) {
+            Entry::Occupied(entry) => {
+                let slot = &mut elements[*entry.get()];
+                *slot = slot
+                    .take()
+                    .map(|base_element| strategy(base_element, patch_element));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(elements.len());
+                elements.push(Some(patch_element));
+            }
+        }
+    }
+
+    elements.into_iter().flatten().collect()
+}
@@ -0,0 +1,110 @@
+use std::{io, thread, time::Duration};
+
+/// A policy for retrying IO operations that failed due to transient errors.
+///
+/// On network filesystems and while other processes (e.g. antivirus scanners) hold a file open,
+/// writes may fail intermittently. A `RetryPolicy` retries such operations with exponential backoff:
+/// the first retry waits `backoff`, and every further retry waits twice as long as the previous one.
+///
+/// Only transient errors are retried, see [RetryPolicy::is_transient]. Permanent errors are returned immediately.
+///
+/// The default policy makes a single attempt and never retries.
+///
+/// # Fields
+///
+/// * `attempts` - The maximum number of attempts, including the first one. `0` is treated like `1`.
+/// * `backoff` - The time to wait before the first retry.
+///
+/// # Examples
+///
+/// ```
+/// use std::{io, time::Duration};
+/// use lum_config::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(3, Duration::from_millis(1));
+///
+/// let mut failures = 2;
+/// let result = policy.run(|| {
+///     if failures > 0 {
+///         failures -= 1;
+///         return Err(io::Error::from(io::ErrorKind::Interrupted));
+///     }
+///     Ok("written")
+/// });
+///
+/// assert_eq!(result.unwrap(), "written");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a new `RetryPolicy` making at most `attempts` attempts, waiting `backoff` before the first retry.
+    pub fn new(attempts: u32, backoff: Duration) -> Self {
+        RetryPolicy { attempts, backoff }
+    }
+
+    /// Runs `operation`, retrying it according to this policy as long as it fails with a transient error.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The IO operation to run.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the result of the first successful attempt.
+    /// * Failure is indicated by an `Err` value, containing the error of the last attempt, or the first permanent error.
+    pub fn run<T, Operation>(&self, mut operation: Operation) -> Result<T, io::Error>
+    where
+        Operation: FnMut() -> Result<T, io::Error>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match operation() {
+                Err(error) if attempt < self.attempts && Self::is_transient(&error) => {
+                    thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns whether `error` is transient, meaning that retrying the operation may succeed.
+    ///
+    /// Interrupted and timed out operations, operations that would block, busy resources (`EBUSY`),
+    /// as well as sharing and lock violations on Windows are transient.
+    /// All other errors, e.g. missing permissions or a missing file, are permanent.
+    pub fn is_transient(error: &io::Error) -> bool {
+        if matches!(
+            error.kind(),
+            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ) {
+            return true;
+        }
+
+        // `io::ErrorKind::ResourceBusy` requires Rust 1.83.
+        #[cfg(unix)]
+        let transient_codes: &[i32] = &[16];
+        // ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+        #[cfg(windows)]
+        let transient_codes: &[i32] = &[32, 33];
+        #[cfg(not(any(unix, windows)))]
+        let transient_codes: &[i32] = &[];
+
+        error
+            .raw_os_error()
+            .is_some_and(|code| transient_codes.contains(&code))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(1, Duration::ZERO)
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::{Arc, RwLock};
+
+/// A configuration shared between threads, which can be replaced atomically, e.g. on reload.
+///
+/// Readers get an `Arc` of the current configuration from [SharedConfig::load], which stays valid and unchanged
+/// even if the configuration is replaced afterwards. [SharedConfig::reload] and [SharedConfig::store] swap in a new version.
+///
+/// Internally, the current `Arc` is guarded by an `RwLock`, which is only held to clone or replace the `Arc`.
+/// Reads therefore never wait for a reload to parse the configuration, only for the swap itself.
+///
+/// # Examples
+///
+/// ```
+/// use lum_libs::serde::{Deserialize, Serialize};
+/// use lum_config::{InMemoryHandler, SharedConfig};
+///
+/// #[derive(Default, Serialize, Deserialize)]
+/// #[serde(default)]
+/// struct Config {
+///     port: u16,
+/// }
+///
+/// let handler = InMemoryHandler::<Config>::new().with_document(r#"{"port": 80}"#);
+/// let shared = SharedConfig::new(handler.load_config().unwrap());
+/// let before = shared.load();
+///
+/// handler.save_config(&Config { port: 8080 }).unwrap();
+/// shared.reload(|| handler.load_config()).unwrap();
+///
+/// assert_eq!(before.port, 80);
+/// assert_eq!(shared.load().port, 8080);
+/// ```
+#[derive(Debug)]
+pub struct SharedConfig<Config> {
+    current: RwLock<Arc<Config>>,
+}
+
+impl<Config> SharedConfig<Config> {
+    /// Creates a new `SharedConfig` holding `config`.
+    pub fn new(config: Config) -> Self {
+        SharedConfig {
+            current: RwLock::new(Arc::new(config)),
+        }
+    }
+
+    /// Returns the current configuration.
+    pub fn load(&self) -> Arc<Config> {
+        let current = self
+            .current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        Arc::clone(&current)
+    }
+
+    /// Replaces the current configuration with `config`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The new configuration.
+    ///
+    /// # Returns
+    ///
+    /// The previous configuration.
+    pub fn store(&self, config: Config) -> Arc<Config> {
+        let mut current = self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        std::mem::replace(&mut current, Arc::new(config))
+    }
+
+    /// Loads a new configuration with `loader`, and replaces the current configuration with it.
+    ///
+    /// The current configuration is kept if `loader` fails. `loader` runs without holding the lock, so reads are not blocked while it runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `loader` - A closure loading the new configuration, e.g. calling `load_config` of a handler.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` indicating success or failure.
+    /// * Success is indicated by an `Ok` value, containing the new configuration.
+    /// * Failure is indicated by an `Err` value, containing the error returned by `loader`.
+    pub fn reload<Error, Loader>(&self, loader: Loader) -> Result<Arc<Config>, Error>
+    where
+        Loader: FnOnce() -> Result<Config, Error>,
+    {
+        let config = Arc::new(loader()?);
+        let mut current = self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = Arc::clone(&config);
+
+        Ok(config)
+    }
+}
+
+impl<Config: Default> Default for SharedConfig<Config> {
+    fn default() -> Self {
+        SharedConfig::new(Config::default())
+    }
+}
@@ -1,8 +1,17 @@
-use std::{env, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
 
-use lum_config::MergeFrom;
+use lum_config::{Codec, FileMetadata, FileSystem, MergeFrom, TryMergeFrom};
 use lum_libs::{
+    log::{self, Log, Metadata, Record},
     serde::{Deserialize, Serialize},
+    serde_json,
     uuid::Uuid,
 };
 
@@ -105,3 +114,415 @@ pub fn get_temp_dir() -> PathBuf {
 
     temp_dir.join(uuid)
 }
+
+/// A logger collecting all log messages, to assert on warnings.
+pub struct CapturingLogger {
+    messages: Mutex<Vec<String>>,
+}
+
+pub static LOGGER: CapturingLogger = CapturingLogger {
+    messages: Mutex::new(Vec::new()),
+};
+
+impl Log for CapturingLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.messages
+            .lock()
+            .unwrap()
+            .push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs [LOGGER] and returns all messages logged so far.
+pub fn captured_messages() -> Vec<String> {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(log::LevelFilter::Trace);
+    }
+
+    LOGGER.messages.lock().unwrap().clone()
+}
+
+pub struct CompactJsonCodec;
+
+impl Codec for CompactJsonCodec {
+    type Error = serde_json::Error;
+
+    fn to_string<T: Serialize>(&self, value: &T) -> Result<String, Self::Error> {
+        serde_json::to_string(value)
+    }
+
+    fn from_str<T: for<'de> Deserialize<'de>>(&self, input: &str) -> Result<T, Self::Error> {
+        serde_json::from_str(input)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MapConfig {
+    pub zebra: String,
+    pub entries: HashMap<String, HashMap<String, u32>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PortConfig {
+    pub port: Option<u16>,
+}
+
+impl MergeFrom<PortConfig> for PortConfig {
+    fn merge_from(self, other: PortConfig) -> Self {
+        PortConfig {
+            port: other.port.or(self.port),
+        }
+    }
+}
+
+impl MergeFrom<PortConfig> for FileConfig {
+    fn merge_from(self, other: PortConfig) -> Self {
+        FileConfig {
+            value: self.value,
+            env_config_variable: match other.port {
+                Some(port) => port.to_string(),
+                None => self.env_config_variable,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenerConfig {
+    pub unix_socket: Option<String>,
+    pub tcp_port: Option<u16>,
+}
+
+impl TryMergeFrom<ListenerConfig> for ListenerConfig {
+    type Error = String;
+
+    fn try_merge_from(self, other: ListenerConfig) -> Result<Self, Self::Error> {
+        Ok(ListenerConfig {
+            unix_socket: other.unix_socket.or(self.unix_socket),
+            tcp_port: other.tcp_port.or(self.tcp_port),
+        })
+    }
+}
+
+pub fn validate_listener(config: &ListenerConfig) -> Result<(), String> {
+    match (&config.unix_socket, config.tcp_port) {
+        (Some(_), Some(_)) => Err("unix_socket and tcp_port are mutually exclusive".to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostsConfig {
+    pub hosts: Vec<String>,
+    pub ports: Vec<u16>,
+    pub fallbacks: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub name: String,
+    pub port: u16,
+    pub verbose: bool,
+}
+
+impl TryMergeFrom<PortConfig> for ServerConfig {
+    type Error = String;
+
+    fn try_merge_from(self, other: PortConfig) -> Result<Self, Self::Error> {
+        match other.port {
+            Some(0) => Err("port must not be 0".to_string()),
+            Some(port) => Ok(ServerConfig { port, ..self }),
+            None => Ok(self),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PoolConfig {
+    pub min: u32,
+    pub max: u32,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub host: String,
+    pub user: String,
+    pub pool: PoolConfig,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseAppConfig {
+    pub name: String,
+    pub database: DatabaseConfig,
+    pub replica: Option<DatabaseConfig>,
+}
+
+#[cfg(feature = "human-values")]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitsConfig {
+    #[serde(with = "lum_config::config_serde::duration")]
+    pub timeout: std::time::Duration,
+    #[serde(with = "lum_config::config_serde::byte_size")]
+    pub max_size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequiredFieldConfig {
+    pub name: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PartialServerConfig {
+    pub name: Option<String>,
+    pub port: Option<u16>,
+    pub verbose: Option<bool>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PartialNestedConfig {
+    pub value: Option<String>,
+    pub file_config: Option<PartialFileConfig>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PartialFileConfig {
+    pub value: Option<String>,
+    pub env_config_variable: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlattenedConfig {
+    pub label: String,
+    #[serde(flatten)]
+    pub server: ServerConfig,
+    pub listener: ListenerConfig,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum InternallyTaggedBackend {
+    Memory { capacity: u32 },
+    Redis { url: String, database: u8 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "config", rename_all = "lowercase")]
+pub enum AdjacentlyTaggedBackend {
+    Memory,
+    File { path: String },
+    Redis { url: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendConfig {
+    pub name: String,
+    pub internal: InternallyTaggedBackend,
+    pub adjacent: AdjacentlyTaggedBackend,
+    pub fallback: AdjacentlyTaggedBackend,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        BackendConfig {
+            name: "cache".to_string(),
+            internal: InternallyTaggedBackend::Memory { capacity: 100 },
+            adjacent: AdjacentlyTaggedBackend::File {
+                path: "cache.db".to_string(),
+            },
+            fallback: AdjacentlyTaggedBackend::Memory,
+        }
+    }
+}
+
+/// An in-memory [FileSystem] that records every operation as `"<operation> <path>"`.
+///
+/// Directories exist once a file was added below them or they were created. Every path is on device `0`, unless it is below a mount.
+#[derive(Debug, Default)]
+pub struct RecordingFs {
+    files: Mutex<BTreeMap<PathBuf, String>>,
+    directories: Mutex<BTreeSet<PathBuf>>,
+    mounts: Vec<(PathBuf, u64)>,
+    operations: Mutex<Vec<String>>,
+}
+
+impl RecordingFs {
+    pub fn with_file<IntoPathBuf: Into<PathBuf>>(self, path: IntoPathBuf, content: &str) -> Self {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            self.add_directories(parent);
+        }
+        self.files.lock().unwrap().insert(path, content.to_string());
+        self
+    }
+
+    pub fn with_directory<IntoPathBuf: Into<PathBuf>>(self, path: IntoPathBuf) -> Self {
+        self.add_directories(&path.into());
+        self
+    }
+
+    pub fn with_mount<IntoPathBuf: Into<PathBuf>>(
+        mut self,
+        path: IntoPathBuf,
+        device: u64,
+    ) -> Self {
+        self.mounts.push((path.into(), device));
+        self
+    }
+
+    pub fn file(&self, path: &Path) -> Option<String> {
+        self.files.lock().unwrap().get(path).cloned()
+    }
+
+    pub fn operations(&self) -> Vec<String> {
+        self.operations.lock().unwrap().clone()
+    }
+
+    fn add_directories(&self, path: &Path) {
+        let mut directories = self.directories.lock().unwrap();
+        for ancestor in path.ancestors() {
+            directories.insert(ancestor.to_path_buf());
+        }
+    }
+
+    fn device(&self, path: &Path) -> u64 {
+        self.mounts
+            .iter()
+            .filter(|(mount, _)| path.starts_with(mount))
+            .max_by_key(|(mount, _)| mount.components().count())
+            .map_or(0, |(_, device)| *device)
+    }
+
+    fn record(&self, operation: &str, path: &Path) {
+        self.operations
+            .lock()
+            .unwrap()
+            .push(format!("{operation} {}", path.display()));
+    }
+}
+
+impl FileSystem for RecordingFs {
+    fn read(&self, path: &Path, limit: u64) -> Result<Vec<u8>, io::Error> {
+        self.record("read", path);
+        let content = self.file(path).ok_or(io::ErrorKind::NotFound)?;
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+
+        Ok(content.into_bytes().into_iter().take(limit).collect())
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        self.record("write", path);
+        let content = String::from_utf8(contents.to_vec())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), content);
+
+        Ok(())
+    }
+
+    fn write_new(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        if self.file(path).is_some() {
+            self.record("write_new", path);
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+
+        self.write(path, contents)
+    }
+
+    fn sync(&self, path: &Path) -> Result<(), io::Error> {
+        self.record("sync", path);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), io::Error> {
+        self.record("create_dir_all", path);
+        self.add_directories(path);
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata, io::Error> {
+        let len = match self.file(path) {
+            Some(content) => Some(content.len() as u64),
+            None if self.directories.lock().unwrap().contains(path) => None,
+            None => return Err(io::ErrorKind::NotFound.into()),
+        };
+
+        Ok(FileMetadata {
+            is_file: len.is_some(),
+            len: len.unwrap_or_default(),
+            readonly: false,
+            device: Some(self.device(path)),
+            modified: SystemTime::UNIX_EPOCH,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        self.record("read_dir", path);
+        Ok(self
+            .files
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|file| file.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), io::Error> {
+        self.record("rename", from);
+        let mut files = self.files.lock().unwrap();
+        let content = files.remove(from).ok_or(io::ErrorKind::NotFound)?;
+        files.insert(to.to_path_buf(), content);
+
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> Result<(), io::Error> {
+        self.record("remove", path);
+        self.files
+            .lock()
+            .unwrap()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+}
+
+/// A writer whose clones share the written bytes, to inspect what a `FileHandler` wrote to stdout.
+#[derive(Debug, Default, Clone)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -2,9 +2,24 @@ pub mod common;
 
 #[cfg(test)]
 mod tests {
-    use std::fs;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        env, fs, io,
+        path::Path,
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
 
-    use lum_config::{merger, FileHandler};
+    use lum_config::{
+        env_handler, file_handler, load, load_from_store, load_lenient, load_with_metadata,
+        load_with_options, merger, Codec, ConfigLoadError, ConfigPathError, ConfigSaveError,
+        ConfigStore, EmptyFilePolicy, EnvHandler, EnvHandlerBuildError, EnvVarDoc,
+        EnvironmentConfigParseError, FileConfigParseError, FileHandler, InMemoryHandler, JsonCodec,
+        JsonIndent, JsonStyle, Leniency, LoadHooks, LoadOptions, MergeExt, OverlayTarget,
+        PathStatus, RetryPolicy, SharedConfig, Strictness, TryMergeExt,
+    };
+    use lum_libs::serde_json::{self, Value};
 
     use crate::common::{self};
 
@@ -72,4 +87,3048 @@ mod tests {
             common::FILE_CONFIG_VALUE_SET
         );
     }
+
+    #[test]
+    fn merge_extension_methods() {
+        let merged_config = common::FileConfig::default()
+            .merge_with(common::EnvConfig::default())
+            .merge_with(common::PortConfig { port: Some(8080) });
+        assert_eq!(merged_config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(merged_config.env_config_variable, "8080");
+
+        let result = common::ListenerConfig::default()
+            .try_merge_with(common::ListenerConfig {
+                unix_socket: Some("/run/lum.sock".to_string()),
+                tcp_port: None,
+            })
+            .and_then(|config| config.try_merge_with(common::ListenerConfig::default()));
+        assert_eq!(
+            result.unwrap().unix_socket.as_deref(),
+            Some("/run/lum.sock")
+        );
+    }
+
+    #[test]
+    fn custom_codec() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig, common::CompactJsonCodec> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_codec(common::CompactJsonCodec);
+        let file_config = file_handler.load_config().unwrap();
+        assert_eq!(file_config.value, common::FILE_CONFIG_VALUE_SET);
+
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        assert!(!content.contains('\n'));
+
+        fs::write(&file_handler.config_file_path, "not a config").unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(result, Err(FileConfigParseError::Codec(_))));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn preserve_unknown_keys() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_preserve_unknown(true);
+        file_handler.create_config_directory().unwrap();
+        fs::write(
+            &file_handler.config_file_path,
+            r#"{"value": "Custom", "newer_setting": {"enabled": true}}"#,
+        )
+        .unwrap();
+
+        let file_config = file_handler.load_config().unwrap();
+        assert_eq!(file_config.value, "Custom");
+
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let document: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(document["newer_setting"]["enabled"], Value::Bool(true));
+        assert_eq!(document["value"], Value::from("Custom"));
+        assert_eq!(
+            document["env_config_variable"],
+            Value::from(common::ENV_CONFIG_VALUE_NOT_SET)
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn drop_unknown_keys_by_default() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        file_handler.create_config_directory().unwrap();
+        fs::write(&file_handler.config_file_path, r#"{"newer_setting": 1}"#).unwrap();
+
+        file_handler.load_config().unwrap();
+
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let document: Value = serde_json::from_str(&content).unwrap();
+        assert!(document.get("newer_setting").is_none());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn json_styles() {
+        let value = serde_json::json!({ "key": [1] });
+
+        let default = JsonCodec::default().to_string(&value).unwrap();
+        assert_eq!(default, "{\n  \"key\": [\n    1\n  ]\n}");
+
+        let four_spaces = JsonCodec::new(JsonStyle::spaces(4))
+            .to_string(&value)
+            .unwrap();
+        assert_eq!(four_spaces, "{\n    \"key\": [\n        1\n    ]\n}");
+
+        let tabs = JsonCodec::new(JsonStyle::tabs()).to_string(&value).unwrap();
+        assert_eq!(tabs, "{\n\t\"key\": [\n\t\t1\n\t]\n}");
+
+        let compact = JsonCodec::new(JsonStyle::compact())
+            .to_string(&value)
+            .unwrap();
+        assert_eq!(compact, "{\"key\":[1]}");
+
+        let compact_ignores_indent = JsonStyle {
+            indent: JsonIndent::Tabs,
+            compact: true,
+        };
+        let compact = JsonCodec::new(compact_ignores_indent)
+            .to_string(&value)
+            .unwrap();
+        assert_eq!(compact, "{\"key\":[1]}");
+    }
+
+    #[test]
+    fn file_handler_json_style() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::EnvConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_json_style(JsonStyle::compact());
+        file_handler.load_config().unwrap();
+
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        assert_eq!(
+            content,
+            format!("{{\"value\":\"{}\"}}", common::ENV_CONFIG_VALUE_SET)
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn sorted_keys() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::MapConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_json_style(JsonStyle::compact())
+                .with_sorted_keys(true);
+
+        let nested: HashMap<String, u32> = ["d", "b", "c", "a"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, key)| (key.to_string(), index as u32))
+            .collect();
+        let entries = ["y", "x"]
+            .into_iter()
+            .map(|key| (key.to_string(), nested.clone()))
+            .collect();
+        let config = common::MapConfig {
+            zebra: "z".to_string(),
+            entries,
+        };
+        file_handler.save_config(&config).unwrap();
+
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let nested = r#"{"a":3,"b":1,"c":2,"d":0}"#;
+        assert_eq!(
+            content,
+            format!(r#"{{"entries":{{"x":{nested},"y":{nested}}},"zebra":"z"}}"#)
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn readonly_tolerant() {
+        use std::{fs::OpenOptions, os::unix::fs::PermissionsExt};
+
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        file_handler.create_config_directory().unwrap();
+        fs::write(&file_handler.config_file_path, "{}").unwrap();
+
+        let permissions = fs::Permissions::from_mode(0o444);
+        fs::set_permissions(&file_handler.config_file_path, permissions).unwrap();
+        let writable = OpenOptions::new()
+            .write(true)
+            .open(&file_handler.config_file_path)
+            .is_ok();
+
+        // Privileged users (e.g. root in CI containers) can write read-only files, so there is nothing to test.
+        if !writable {
+            assert!(file_handler.load_config().is_err());
+
+            let file_handler = file_handler.with_readonly_tolerant(true);
+            let file_config = file_handler.load_config().unwrap();
+            assert_eq!(file_config.value, common::FILE_CONFIG_VALUE_SET);
+            assert_eq!(
+                fs::read_to_string(&file_handler.config_file_path).unwrap(),
+                "{}"
+            );
+        }
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_merges_env_into_file() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_LOAD_PORT", "8080");
+
+        let config: common::FileConfig =
+            load::<_, _, common::PortConfig>("lum_load", Some(temp_str), None).unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, "8080");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_lenient_skips_env() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_LENIENT_ENV_PORT", "not a port");
+
+        let result = load_lenient::<_, common::FileConfig, common::PortConfig>(
+            "lum_lenient_env",
+            Some(temp_str),
+            None,
+            Leniency::default(),
+        );
+        assert!(matches!(result, Err(ConfigLoadError::ParseEnv(_))));
+
+        let leniency = Leniency {
+            env: true,
+            file: false,
+        };
+        let config = load_lenient::<_, common::FileConfig, common::PortConfig>(
+            "lum_lenient_env",
+            Some(temp_str),
+            None,
+            leniency,
+        )
+        .unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, common::ENV_CONFIG_VALUE_NOT_SET);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_lenient_skips_file() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_LENIENT_FILE_PORT", "8080");
+        let config_path = temp_dir.join("lum_lenient_file");
+        fs::create_dir_all(&config_path).unwrap();
+        fs::write(config_path.join("config.json"), "not json").unwrap();
+
+        let result = load_lenient::<_, common::FileConfig, common::PortConfig>(
+            "lum_lenient_file",
+            Some(temp_str),
+            None,
+            Leniency::default(),
+        );
+        assert!(matches!(result, Err(ConfigLoadError::ParseFile(_))));
+
+        let leniency = Leniency {
+            env: false,
+            file: true,
+        };
+        let config = load_lenient::<_, common::FileConfig, common::PortConfig>(
+            "lum_lenient_file",
+            Some(temp_str),
+            None,
+            leniency,
+        )
+        .unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, "8080");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_lenient_fails_when_all_sources_fail() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_LENIENT_ALL_PORT", "not a port");
+        let config_path = temp_dir.join("lum_lenient_all");
+        fs::create_dir_all(&config_path).unwrap();
+        fs::write(config_path.join("config.json"), "not json").unwrap();
+
+        let leniency = Leniency {
+            env: true,
+            file: true,
+        };
+        let result = load_lenient::<_, common::FileConfig, common::PortConfig>(
+            "lum_lenient_all",
+            Some(temp_str),
+            None,
+            leniency,
+        );
+        assert!(matches!(result, Err(ConfigLoadError::AllSources { .. })));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn in_memory_handler() {
+        let handler: InMemoryHandler<common::FileConfig> = InMemoryHandler::new();
+        assert!(handler.document().is_none());
+
+        let file_config = handler.load_config().unwrap();
+        assert_eq!(file_config.value, common::FILE_CONFIG_VALUE_SET);
+        assert!(handler
+            .document()
+            .unwrap()
+            .contains(common::FILE_CONFIG_VALUE_SET));
+
+        let shared = handler.clone();
+        let mut file_config = shared.load_config().unwrap();
+        file_config.value = "Changed".to_string();
+        shared.save_config(&file_config).unwrap();
+        assert_eq!(handler.load_config().unwrap().value, "Changed");
+
+        let handler: InMemoryHandler<common::FileConfig> =
+            InMemoryHandler::new().with_document(r#"{"value": "Preset"}"#);
+        let file_config = handler.load_config().unwrap();
+        assert_eq!(file_config.value, "Preset");
+        assert_eq!(
+            file_config.env_config_variable,
+            common::ENV_CONFIG_VALUE_NOT_SET
+        );
+    }
+
+    fn store_round_trip(store: &dyn ConfigStore<common::FileConfig>) {
+        store.delete().unwrap();
+        assert!(!store.exists().unwrap());
+
+        let mut file_config = store.load().unwrap();
+        assert!(store.exists().unwrap());
+
+        file_config.value = "Stored".to_string();
+        store.save(&file_config).unwrap();
+        assert_eq!(store.load().unwrap().value, "Stored");
+
+        store.delete().unwrap();
+        assert!(!store.exists().unwrap());
+        store.delete().unwrap();
+    }
+
+    #[test]
+    fn config_store() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        store_round_trip(&file_handler);
+
+        let memory_handler: InMemoryHandler<common::FileConfig> = InMemoryHandler::new();
+        store_round_trip(&memory_handler);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_from_store_merges_env() {
+        env::set_var("LUM_STORE_PORT", "9000");
+        let store: InMemoryHandler<common::FileConfig> = InMemoryHandler::new();
+
+        let config =
+            load_from_store::<_, common::FileConfig, common::PortConfig>("lum_store", &store)
+                .unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, "9000");
+    }
+
+    #[test]
+    fn env_additional_prefix_both_present() {
+        env::set_var("LUM_NEW_BOTH_VALUE", "New");
+        env::set_var("LUM_OLD_BOTH_VALUE", "Old");
+
+        let env_handler: EnvHandler<common::EnvConfig> =
+            EnvHandler::new("lum_new_both").with_additional_prefix("lum_old_both");
+        let env_config = env_handler.load_config().unwrap();
+        assert_eq!(env_config.value.unwrap(), "New");
+    }
+
+    #[test]
+    fn env_additional_prefix_only_legacy() {
+        env::set_var("LUM_OLD_LEGACY_VALUE", "Old");
+
+        let env_handler: EnvHandler<common::EnvConfig> =
+            EnvHandler::new("lum_new_legacy").with_additional_prefix("lum_old_legacy");
+        let env_config = env_handler.load_config().unwrap();
+        assert_eq!(env_config.value.unwrap(), "Old");
+    }
+
+    #[test]
+    fn env_additional_prefix_only_new() {
+        env::set_var("LUM_NEW_ONLY_VALUE", "New");
+
+        let env_handler: EnvHandler<common::EnvConfig> =
+            EnvHandler::new("lum_new_only").with_additional_prefix("lum_old_only");
+        let env_config = env_handler.load_config().unwrap();
+        assert_eq!(env_config.value.unwrap(), "New");
+    }
+
+    #[test]
+    fn env_prefix_sanitization() {
+        let prefix =
+            |app_name: &str| EnvHandler::<common::EnvConfig>::new(app_name).resolved_prefix();
+
+        assert_eq!(prefix("Lum Bot"), "LUM_BOT");
+        assert_eq!(prefix("lum-bot"), "LUM_BOT");
+        assert_eq!(prefix("Lüm"), "L_M");
+        assert_eq!(prefix("lum_bot2"), "LUM_BOT2");
+    }
+
+    #[test]
+    fn env_explicit_prefix() {
+        env::set_var("LUM_EXPLICIT_VALUE", "Explicit");
+
+        let env_handler: EnvHandler<common::EnvConfig> =
+            EnvHandler::new("Some App Name").with_prefix("LUM_EXPLICIT");
+        assert_eq!(env_handler.resolved_prefix(), "LUM_EXPLICIT");
+
+        let env_config = env_handler.load_config().unwrap();
+        assert_eq!(env_config.value.unwrap(), "Explicit");
+    }
+
+    #[test]
+    fn env_sanitized_prefix() {
+        env::set_var("LUM_SANITIZED_BOT_VALUE", "Sanitized");
+
+        let env_handler: EnvHandler<common::EnvConfig> = EnvHandler::new("Lum Sanitized-Bot");
+        let env_config = env_handler.load_config().unwrap();
+        assert_eq!(env_config.value.unwrap(), "Sanitized");
+    }
+
+    #[test]
+    fn load_returns_metadata() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+
+        let (_, metadata) = load_with_metadata::<_, common::FileConfig, common::PortConfig>(
+            "lum metadata",
+            Some(temp_str),
+            None,
+        )
+        .unwrap();
+        assert_eq!(metadata.env_prefix, "LUM_METADATA");
+        assert_eq!(
+            metadata.config_file_path,
+            temp_dir.join("lum metadata").join("config.json")
+        );
+        assert!(metadata.created_config_file);
+
+        let (_, metadata) = load_with_metadata::<_, common::FileConfig, common::PortConfig>(
+            "lum metadata",
+            Some(temp_str),
+            None,
+        )
+        .unwrap();
+        assert!(!metadata.created_config_file);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn config_path_is_a_directory() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        fs::create_dir_all(&file_handler.config_file_path).unwrap();
+
+        let result = file_handler.load_config();
+        assert!(
+            matches!(result, Err(FileConfigParseError::NotAFile(path)) if path == file_handler.config_file_path)
+        );
+
+        let result = file_handler.save_config(&common::FileConfig::default());
+        assert!(
+            matches!(result, Err(ConfigSaveError::NotAFile(path)) if path == file_handler.config_file_path)
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn file_metadata() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        assert!(file_handler.modified().unwrap().is_none());
+        assert!(file_handler.size().unwrap().is_none());
+        assert!(!file_handler.config_directory_path.exists());
+
+        file_handler.load_config().unwrap();
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        assert!(file_handler.modified().unwrap().is_some());
+        assert_eq!(file_handler.size().unwrap(), Some(content.len() as u64));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn xdg_config_home() {
+        let temp_dir = common::get_temp_dir();
+        // XDG_CONFIG_HOME is process-wide, so restore it right away to not affect other tests running in parallel
+        let previous = env::var_os("XDG_CONFIG_HOME");
+        env::set_var("XDG_CONFIG_HOME", &temp_dir);
+        let file_handler = FileHandler::<common::FileConfig>::new_xdg("lum_xdg_config_home", None);
+        match previous {
+            Some(previous) => env::set_var("XDG_CONFIG_HOME", previous),
+            None => env::remove_var("XDG_CONFIG_HOME"),
+        }
+
+        let file_handler = file_handler.unwrap();
+        assert_eq!(
+            file_handler.config_file_path,
+            temp_dir.join("lum_xdg_config_home").join("config.json")
+        );
+
+        file_handler.create_config_directory().unwrap();
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_env_only() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_ENV_ONLY_PORT", "7000");
+
+        let options = LoadOptions {
+            use_env: true,
+            use_file: false,
+            ..LoadOptions::default()
+        };
+        let config = load_with_options::<_, common::FileConfig, common::PortConfig>(
+            "lum_env_only",
+            Some(temp_str),
+            None,
+            options,
+        )
+        .unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, "7000");
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn load_file_only() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_FILE_ONLY_PORT", "7000");
+
+        let options = LoadOptions {
+            use_env: false,
+            use_file: true,
+            ..LoadOptions::default()
+        };
+        let config = load_with_options::<_, common::FileConfig, common::PortConfig>(
+            "lum_file_only",
+            Some(temp_str),
+            None,
+            options,
+        )
+        .unwrap();
+        assert_eq!(config.env_config_variable, common::ENV_CONFIG_VALUE_NOT_SET);
+        assert!(temp_dir.join("lum_file_only").join("config.json").exists());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn try_merge_validated() {
+        let base = common::ListenerConfig {
+            unix_socket: Some("/run/lum.sock".to_string()),
+            tcp_port: None,
+        };
+        let patch = common::ListenerConfig {
+            unix_socket: None,
+            tcp_port: Some(8080),
+        };
+        let result = merger::try_merge_validated(base, patch, common::validate_listener);
+        assert_eq!(
+            result.unwrap_err(),
+            "unix_socket and tcp_port are mutually exclusive"
+        );
+
+        let base = common::ListenerConfig::default();
+        let patch = common::ListenerConfig {
+            unix_socket: None,
+            tcp_port: Some(8080),
+        };
+        let merged = merger::try_merge_validated(base, patch, common::validate_listener).unwrap();
+        assert_eq!(merged.tcp_port, Some(8080));
+        assert!(merged.unix_socket.is_none());
+    }
+
+    #[test]
+    fn content_hash() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        assert!(file_handler.content_hash().unwrap().is_none());
+
+        let written_hash = file_handler
+            .save_config_hashed(&common::FileConfig::default())
+            .unwrap();
+        assert_eq!(file_handler.content_hash().unwrap(), Some(written_hash));
+
+        let reformatted = format!(
+            r#"{{ "env_config_variable":"{}",   "value":"{}" }}"#,
+            common::ENV_CONFIG_VALUE_NOT_SET,
+            common::FILE_CONFIG_VALUE_SET
+        );
+        fs::write(&file_handler.config_file_path, reformatted).unwrap();
+        assert_eq!(file_handler.content_hash().unwrap(), Some(written_hash));
+
+        let file_config = common::FileConfig {
+            value: "Edited".to_string(),
+            ..Default::default()
+        };
+        let edited_hash = file_handler.save_config_hashed(&file_config).unwrap();
+        assert_ne!(edited_hash, written_hash);
+        assert_eq!(file_handler.content_hash().unwrap(), Some(edited_hash));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn patch_config() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        file_handler.create_config_directory().unwrap();
+        fs::write(
+            &file_handler.config_file_path,
+            r#"{"value": "Original", "other_tool": {"a": 1, "b": 2}}"#,
+        )
+        .unwrap();
+
+        file_handler
+            .patch(&serde_json::json!({ "value": "Patched", "other_tool": { "b": 3 } }))
+            .unwrap();
+
+        let content = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let document: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(
+            document,
+            serde_json::json!({ "value": "Patched", "other_tool": { "a": 1, "b": 3 } })
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn merge_values() {
+        let mut base = serde_json::json!({ "a": { "b": 1, "c": [1, 2] }, "d": "kept" });
+        let patch = serde_json::json!({ "a": { "c": [3], "e": null }, "f": true });
+
+        merger::merge_values(&mut base, patch);
+        assert_eq!(
+            base,
+            serde_json::json!({ "a": { "b": 1, "c": [3], "e": null }, "d": "kept", "f": true })
+        );
+    }
+
+    #[test]
+    fn list_profiles() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        assert!(file_handler.list_profiles().unwrap().is_empty());
+
+        file_handler.create_config_directory().unwrap();
+        assert!(file_handler.list_profiles().unwrap().is_empty());
+
+        let directory = &file_handler.config_directory_path;
+        for file_name in [
+            "config.json",
+            "config.staging.json",
+            "config.dev.json",
+            "config.toml",
+            "other.test.json",
+        ] {
+            fs::write(directory.join(file_name), "{}").unwrap();
+        }
+        fs::create_dir(directory.join("config.directory.json")).unwrap();
+
+        assert_eq!(
+            file_handler.list_profiles().unwrap(),
+            vec!["dev", "staging"]
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_directory() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let config = file_handler.load_directory().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+
+        let fragments_path = file_handler.config_directory_path.join("conf.d");
+        fs::create_dir(&fragments_path).unwrap();
+        fs::write(
+            fragments_path.join("20-value.json"),
+            r#"{"value": "second"}"#,
+        )
+        .unwrap();
+        fs::write(
+            fragments_path.join("10-value.json"),
+            r#"{"value": "first", "env_config_variable": "fragment"}"#,
+        )
+        .unwrap();
+        fs::write(fragments_path.join("30-value.toml"), "not json").unwrap();
+
+        let config = file_handler.load_directory().unwrap();
+        assert_eq!(config.value, "second");
+        assert_eq!(config.env_config_variable, "fragment");
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn to_redacted_string() {
+        let config = common::FileConfig {
+            value: "secret".to_string(),
+            env_config_variable: "public".to_string(),
+        };
+
+        let output =
+            file_handler::to_redacted_string(&config, &["value", "missing", "value.nested"])
+                .unwrap();
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["value"], "***");
+        assert_eq!(output["env_config_variable"], "public");
+    }
+
+    #[test]
+    fn retry_policy() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(1));
+
+        let mut attempts = 0;
+        let result = policy.run(|| {
+            attempts += 1;
+            if attempts < 3 {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            Ok(attempts)
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        let mut attempts = 0;
+        let result: Result<(), io::Error> = policy.run(|| {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::TimedOut))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(attempts, 3);
+
+        let mut attempts = 0;
+        let result: Result<(), io::Error> = policy.run(|| {
+            attempts += 1;
+            Err(io::Error::from(io::ErrorKind::PermissionDenied))
+        });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    fn save_config_with_retry() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_retry(3, Duration::from_millis(1));
+
+        file_handler
+            .save_config(&common::FileConfig::default())
+            .unwrap();
+        assert!(file_handler.config_file_path.is_file());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_array_indices() {
+        env::set_var("LUM_ARRAY_INDICES_HOSTS_1", "b");
+        env::set_var("LUM_ARRAY_INDICES_HOSTS_0", "a");
+        env::set_var("LUM_ARRAY_INDICES_PORTS_10", "3");
+        env::set_var("LUM_ARRAY_INDICES_PORTS_2", "2");
+        env::set_var("LUM_ARRAY_INDICES_PORTS_0", "1");
+        env::set_var("LUM_ARRAY_INDICES_FALLBACKS", "x,y");
+        env::set_var("LUM_ARRAY_INDICES_FALLBACKS_0", "ignored");
+
+        let env_handler: EnvHandler<common::HostsConfig> =
+            EnvHandler::new("lum_array_indices").with_array_indices(true);
+        let config = env_handler.load_config().unwrap();
+        assert_eq!(config.hosts, vec!["a", "b"]);
+        assert_eq!(config.ports, vec![1, 2, 3]);
+        assert_eq!(config.fallbacks, vec!["x", "y"]);
+
+        let env_handler: EnvHandler<common::HostsConfig> = EnvHandler::new("lum_array_indices");
+        let config = env_handler.load_config().unwrap();
+        assert!(config.hosts.is_empty());
+    }
+
+    #[test]
+    fn load_config_recover() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        file_handler.create_config_directory().unwrap();
+        fs::write(&file_handler.config_file_path, r#"{"value": "kept"}"#).unwrap();
+        let (config, recovery_info) = file_handler.load_config_recover().unwrap();
+        assert_eq!(config.value, "kept");
+        assert!(recovery_info.is_none());
+
+        fs::write(&file_handler.config_file_path, "{ not json").unwrap();
+        let (config, recovery_info) = file_handler.load_config_recover().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+
+        let recovery_info = recovery_info.unwrap();
+        assert!(matches!(
+            recovery_info.error,
+            FileConfigParseError::Codec(_)
+        ));
+        assert_eq!(
+            fs::read_to_string(&recovery_info.corrupt_file_path).unwrap(),
+            "{ not json"
+        );
+        assert!(recovery_info
+            .corrupt_file_path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("config.json.corrupt."));
+        assert!(file_handler.config_file_path.is_file());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn format_config() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_json_style(JsonStyle::compact());
+
+        let output = file_handler
+            .format_config(&common::FileConfig::default())
+            .unwrap();
+        assert!(!output.contains('\n'));
+        assert!(!file_handler.config_file_path.exists());
+
+        let output = file_handler::to_pretty_string(&common::FileConfig::default()).unwrap();
+        assert!(output.contains("\n  \"value\""));
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn interpolation() {
+        env::set_var("LUM_INTERPOLATION_HOST", "example.com");
+        env::set_var("LUM_INTERPOLATION_PORT", "8080");
+        env::remove_var("LUM_INTERPOLATION_MISSING");
+
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::NestedConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_interpolation(true);
+
+        file_handler.create_config_directory().unwrap();
+        let document = r#"{
+            "value": "https://${LUM_INTERPOLATION_HOST}:$LUM_INTERPOLATION_PORT/api",
+            "file_config": {
+                "value": "${LUM_INTERPOLATION_MISSING:-fallback} costs $$5"
+            }
+        }"#;
+        fs::write(&file_handler.config_file_path, document).unwrap();
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "https://example.com:8080/api");
+        let file_config = config.file_config.unwrap();
+        assert_eq!(file_config.value, "fallback costs $5");
+        assert_eq!(
+            file_config.env_config_variable,
+            common::ENV_CONFIG_VALUE_NOT_SET
+        );
+
+        let saved = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        assert!(saved.contains("https://${LUM_INTERPOLATION_HOST}:$LUM_INTERPOLATION_PORT/api"));
+        assert!(saved.contains("env_config_variable"));
+
+        fs::write(
+            &file_handler.config_file_path,
+            r#"{"value": "${LUM_INTERPOLATION_MISSING}"}"#,
+        )
+        .unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(
+            result,
+            Err(FileConfigParseError::UnresolvedInterpolation(name)) if name == "LUM_INTERPOLATION_MISSING"
+        ));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_handler_builder() {
+        env::set_var("LUM_BUILDER_HOSTS_0", "a");
+        env::set_var("LUM_BUILDER_OLD_PORTS", "1,2");
+
+        let env_handler: EnvHandler<common::HostsConfig> = EnvHandler::builder("lum-builder")
+            .additional_prefix("lum_builder_old")
+            .array_indices(true)
+            .build()
+            .unwrap();
+        let config = env_handler.load_config().unwrap();
+        assert_eq!(config.hosts, vec!["a"]);
+        assert_eq!(config.ports, vec![1, 2]);
+
+        let result = EnvHandler::<common::HostsConfig>::builder("lum_builder")
+            .prefix("")
+            .build();
+        assert!(matches!(result, Err(EnvHandlerBuildError::EmptyPrefix)));
+
+        let result = EnvHandler::<common::HostsConfig>::builder("lum_builder")
+            .additional_prefix("LUM-BUILDER")
+            .build();
+        assert!(matches!(
+            result,
+            Err(EnvHandlerBuildError::DuplicatePrefix(prefix)) if prefix == "LUM_BUILDER"
+        ));
+    }
+
+    #[test]
+    fn load_with_options_allow_missing_file_layer() {
+        env::set_var("LUM_MISSING_FILE_LAYER_PORT", "8080");
+
+        let temp_dir = common::get_temp_dir();
+        fs::write(&temp_dir, "not a directory").unwrap();
+        let config_directory = temp_dir.join("config").to_str().unwrap().to_string();
+
+        let result = load_with_options::<_, common::FileConfig, common::PortConfig>(
+            "lum_missing_file_layer".to_string(),
+            Some(config_directory.clone()),
+            None,
+            LoadOptions::default(),
+        );
+        assert!(matches!(
+            result,
+            Err(ConfigLoadError::ParseFile(FileConfigParseError::IO(_)))
+        ));
+
+        let options = LoadOptions {
+            allow_missing_file_layer: true,
+            ..LoadOptions::default()
+        };
+        let config = load_with_options::<_, common::FileConfig, common::PortConfig>(
+            "lum_missing_file_layer".to_string(),
+            Some(config_directory),
+            None,
+            options,
+        )
+        .unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, "8080");
+
+        fs::remove_file(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn render_with_source() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let source = "{\n  \"value\": \"a\",\n  \"env_config_variable\": 5\n}";
+        file_handler.create_config_directory().unwrap();
+        fs::write(&file_handler.config_file_path, source).unwrap();
+
+        let error = file_handler.load_config().unwrap_err();
+        assert_eq!(error.location(), Some((3, 26)));
+
+        let rendered = error.render_with_source(source);
+        let expected = [
+            " --> line 3, column 26",
+            "  |",
+            "1 | {",
+            "2 |   \"value\": \"a\",",
+            "3 |   \"env_config_variable\": 5",
+            "  |                          ^",
+            "4 | }",
+        ]
+        .join("\n");
+        assert!(rendered.starts_with(&error.to_string()));
+        assert!(rendered.ends_with(&expected));
+
+        let error = FileConfigParseError::NotAFile(temp_dir.clone());
+        assert_eq!(error.render_with_source(source), error.to_string());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn bootstrap() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_bootstrap(|| r#"{"value": "bootstrapped"}"#.to_string());
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "bootstrapped");
+        assert_eq!(config.env_config_variable, common::ENV_CONFIG_VALUE_NOT_SET);
+
+        fs::write(&file_handler.config_file_path, r#"{"value": "existing"}"#).unwrap();
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "existing");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn to_env_string_round_trip() {
+        let config = common::HostsConfig {
+            hosts: vec!["a.example.com".to_string(), "b.example.com".to_string()],
+            ports: vec![80, 443],
+            fallbacks: vec!["c".to_string()],
+        };
+
+        let output = env_handler::to_env_string(&config, "LUM_ENV_ROUND_TRIP").unwrap();
+        for line in output.lines() {
+            let (name, value) = line.split_once('=').unwrap();
+            env::set_var(name, value);
+        }
+
+        let env_handler: EnvHandler<common::HostsConfig> = EnvHandler::new("lum_env_round_trip");
+        assert_eq!(env_handler.load_config().unwrap(), config);
+
+        let config = common::NestedConfig::default();
+        let output = env_handler::to_env_string(&config, "LUM_ENV_ROUND_TRIP_NESTED").unwrap();
+        assert_eq!(
+            output,
+            format!("LUM_ENV_ROUND_TRIP_NESTED_VALUE={}\n", config.value)
+        );
+
+        let config = common::HostsConfig {
+            hosts: vec!["a,b".to_string()],
+            ..common::HostsConfig::default()
+        };
+        let result = env_handler::to_env_string(&config, "LUM_ENV_ROUND_TRIP");
+        assert!(matches!(
+            result,
+            Err(EnvironmentConfigParseError::UnsupportedValue(name)) if name == "LUM_ENV_ROUND_TRIP_HOSTS"
+        ));
+    }
+
+    #[test]
+    fn new_portable() {
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new_portable(Some("portable.json")).unwrap();
+
+        let executable_directory = env::current_exe()
+            .unwrap()
+            .canonicalize()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        assert_eq!(file_handler.config_directory_path, executable_directory);
+        assert_eq!(
+            file_handler.config_file_path,
+            executable_directory.join("portable.json")
+        );
+    }
+
+    #[test]
+    fn merge_with_resolver() {
+        let base = common::NestedConfig {
+            file_config: Some(common::FileConfig::default()),
+            ..common::NestedConfig::default()
+        };
+        let patch = serde_json::json!({
+            "value": "patch",
+            "file_config": {
+                "value": "nested_patch",
+            },
+        });
+
+        let mut conflicts = Vec::new();
+        let merged = merger::merge_with_resolver(base, patch, |conflict| {
+            conflicts.push(conflict.path.join("."));
+            Value::String(format!(
+                "{}+{}",
+                conflict.base.as_str().unwrap(),
+                conflict.patch.as_str().unwrap()
+            ))
+        })
+        .unwrap();
+
+        assert_eq!(conflicts, vec!["file_config.value", "value"]);
+        assert_eq!(
+            merged.value,
+            format!("{}+patch", common::NESTED_CONFIG_VALUE_SET)
+        );
+        let file_config = merged.file_config.unwrap();
+        assert_eq!(
+            file_config.value,
+            format!("{}+nested_patch", common::FILE_CONFIG_VALUE_SET)
+        );
+        assert_eq!(
+            file_config.env_config_variable,
+            common::ENV_CONFIG_VALUE_NOT_SET
+        );
+    }
+
+    #[test]
+    fn max_size() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_max_size(16);
+
+        file_handler.create_config_directory().unwrap();
+        fs::write(&file_handler.config_file_path, r#"{"value": "abc"}"#).unwrap();
+        assert_eq!(file_handler.size().unwrap(), Some(16));
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "abc");
+
+        fs::write(&file_handler.config_file_path, r#"{"value": "abcd"}"#).unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(
+            result,
+            Err(FileConfigParseError::TooLarge { max_size: 16, .. })
+        ));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_config_with_presence() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::NestedConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let (config, presence_map) = file_handler.load_config_with_presence().unwrap();
+        assert_eq!(config.value, common::NESTED_CONFIG_VALUE_SET);
+        assert_eq!(presence_map.paths().count(), 0);
+
+        fs::write(
+            &file_handler.config_file_path,
+            r#"{"file_config": {"value": "set"}, "unknown": 1}"#,
+        )
+        .unwrap();
+        let (config, presence_map) = file_handler.load_config_with_presence().unwrap();
+        assert_eq!(config.file_config.unwrap().value, "set");
+        assert_eq!(
+            presence_map.paths().collect::<Vec<_>>(),
+            vec!["file_config", "file_config.value"]
+        );
+        assert!(!presence_map.contains("value"));
+        assert!(!presence_map.contains("file_config.env_config_variable"));
+        assert!(!presence_map.contains("unknown"));
+
+        let (_, presence_map) = file_handler.load_config_with_presence().unwrap();
+        assert!(presence_map.contains("value"));
+        assert!(presence_map.contains("file_config.env_config_variable"));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn config_load_error_exit_code() {
+        let error = ConfigLoadError::Path(ConfigPathError::UnknownConfigDirectory);
+        assert_eq!(error.exit_code(), 78);
+
+        let error = ConfigLoadError::Path(ConfigPathError::UnknownExecutableDirectory(
+            io::Error::from(io::ErrorKind::NotFound),
+        ));
+        assert_eq!(error.exit_code(), 78);
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::NotFound);
+
+        let error = ConfigLoadError::ParseFile(FileConfigParseError::IO(io::Error::from(
+            io::ErrorKind::PermissionDenied,
+        )));
+        assert_eq!(error.exit_code(), 77);
+        let io_error = io::Error::from(error);
+        assert_eq!(io_error.kind(), io::ErrorKind::PermissionDenied);
+        assert!(io_error
+            .to_string()
+            .starts_with("Unable to parse file config"));
+
+        let error = ConfigLoadError::ParseFile(FileConfigParseError::NotAFile("config".into()));
+        assert_eq!(error.exit_code(), 66);
+
+        let json_error = serde_json::from_str::<Value>("{").unwrap_err();
+        let error = ConfigLoadError::AllSources {
+            env: EnvironmentConfigParseError::UnsupportedValue("KEY".to_string()),
+            file: Box::new(ConfigLoadError::ParseFile(FileConfigParseError::Serde(
+                json_error,
+            ))),
+        };
+        assert_eq!(error.exit_code(), 65);
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn save_config_durable() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_durable(true);
+
+        let config = common::FileConfig {
+            value: "durable".to_string(),
+            ..common::FileConfig::default()
+        };
+        file_handler.save_config(&config).unwrap();
+        file_handler
+            .patch(&serde_json::json!({"env_config_variable": "patched"}))
+            .unwrap();
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "durable");
+        assert_eq!(config.env_config_variable, "patched");
+
+        let entries: Vec<_> = fs::read_dir(&file_handler.config_directory_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec!["config.json"]);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn merge_maps() {
+        let base = HashMap::from([("a", 1), ("b", 2)]);
+        let patch = HashMap::from([("c", 3)]);
+        let merged = merger::merge_hash_map(base, patch, merger::replace_value);
+        assert_eq!(merged, HashMap::from([("a", 1), ("b", 2), ("c", 3)]));
+
+        let base = BTreeMap::from([("a", 1), ("b", 2)]);
+        let patch = BTreeMap::from([("b", 20), ("c", 30)]);
+        let merged = merger::merge_btree_map(base, patch, merger::replace_value);
+        assert_eq!(merged, BTreeMap::from([("a", 1), ("b", 20), ("c", 30)]));
+
+        let merged = merger::merge_btree_map(
+            BTreeMap::from([("a", 1), ("b", 2)]),
+            BTreeMap::from([("b", 20)]),
+            |base, patch| base + patch,
+        );
+        assert_eq!(merged, BTreeMap::from([("a", 1), ("b", 22)]));
+
+        let port = |port| common::PortConfig { port };
+        let base = HashMap::from([("http", port(Some(80))), ("https", port(Some(443)))]);
+        let patch = HashMap::from([("http", port(Some(8080))), ("https", port(None))]);
+        let merged = merger::merge_hash_map(base, patch, merger::merge);
+        assert_eq!(merged["http"], port(Some(8080)));
+        assert_eq!(merged["https"], port(Some(443)));
+    }
+
+    #[test]
+    fn strictness() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_strictness(Strictness::StrictAfterCreate);
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert!(file_handler.config_file_path.is_file());
+
+        fs::write(&file_handler.config_file_path, "{ invalid").unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(result, Err(FileConfigParseError::Codec(_))));
+
+        let file_handler = file_handler.with_strictness(Strictness::Lenient);
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(
+            fs::read_to_string(&file_handler.config_file_path).unwrap(),
+            "{ invalid"
+        );
+        let (config, presence_map) = file_handler.load_config_with_presence().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(presence_map.paths().count(), 0);
+
+        let file_handler = file_handler.with_strictness(Strictness::Strict);
+        fs::remove_file(&file_handler.config_file_path).unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(
+            result,
+            Err(FileConfigParseError::IO(error)) if error.kind() == io::ErrorKind::NotFound
+        ));
+        assert!(!file_handler.config_file_path.exists());
+
+        fs::remove_dir_all(&temp_dir).unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(
+            result,
+            Err(FileConfigParseError::IO(error)) if error.kind() == io::ErrorKind::NotFound
+        ));
+        assert!(!file_handler.config_directory_path.exists());
+        assert!(!temp_dir.exists());
+    }
+
+    #[test]
+    fn codec_ignores_extension() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), Some("config.conf")).unwrap();
+
+        file_handler.create_config_directory().unwrap();
+        fs::write(&file_handler.config_file_path, r#"{"value": "conf"}"#).unwrap();
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "conf");
+
+        let saved = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let saved: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(saved["value"], "conf");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn reset_to_default() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_preserve_unknown(true);
+
+        file_handler.create_config_directory().unwrap();
+        fs::write(
+            &file_handler.config_file_path,
+            r#"{"value": "custom", "unknown": true}"#,
+        )
+        .unwrap();
+        assert_eq!(file_handler.load_config().unwrap().value, "custom");
+
+        let config = file_handler.reset_to_default().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+
+        let saved = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let saved: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(saved["value"], common::FILE_CONFIG_VALUE_SET);
+        assert!(saved.get("unknown").is_none());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_invalid_variable() {
+        env::set_var("LUM_INVALID_INT_NAME", "server");
+        env::set_var("LUM_INVALID_INT_PORT", "abc");
+        env::set_var("LUM_INVALID_INT_VERBOSE", "true");
+
+        let env_handler: EnvHandler<common::ServerConfig> = EnvHandler::new("lum_invalid_int");
+        let error = env_handler.load_config().unwrap_err();
+        assert!(matches!(
+            &error,
+            EnvironmentConfigParseError::InvalidVariable { name, value, .. }
+                if name == "LUM_INVALID_INT_PORT" && value == "abc"
+        ));
+        assert!(error
+            .to_string()
+            .starts_with("LUM_INVALID_INT_PORT: invalid value 'abc', "));
+
+        env::set_var("LUM_INVALID_BOOL_PORT", "8080");
+        env::set_var("LUM_INVALID_BOOL_VERBOSE", "maybe");
+
+        let env_handler: EnvHandler<common::ServerConfig> = EnvHandler::new("lum_invalid_bool");
+        let error = env_handler.load_config().unwrap_err();
+        assert!(matches!(
+            &error,
+            EnvironmentConfigParseError::InvalidVariable { name, value, .. }
+                if name == "LUM_INVALID_BOOL_VERBOSE" && value == "maybe"
+        ));
+    }
+
+    #[test]
+    fn merge_if_changed() {
+        let base = common::NestedConfig {
+            file_config: Some(common::FileConfig::default()),
+            ..common::NestedConfig::default()
+        };
+
+        let patch = serde_json::json!({
+            "file_config": { "value": common::FILE_CONFIG_VALUE_SET },
+        });
+        let (base, changed) = merger::merge_if_changed(base, patch).unwrap();
+        assert!(!changed);
+
+        let patch = serde_json::json!({
+            "file_config": { "value": "changed" },
+        });
+        let (merged, changed) = merger::merge_if_changed(base, patch).unwrap();
+        assert!(changed);
+        assert_eq!(merged.value, common::NESTED_CONFIG_VALUE_SET);
+        assert_eq!(merged.file_config.unwrap().value, "changed");
+    }
+
+    #[test]
+    fn conditional_overlays() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        file_handler.create_config_directory().unwrap();
+        let directory = file_handler.config_directory_path.clone();
+        fs::write(
+            directory.join("config.linux.json"),
+            r#"{"value": "linux", "env_config_variable": "linux"}"#,
+        )
+        .unwrap();
+        fs::write(
+            directory.join("config.kiosk-1.json"),
+            r#"{"value": "kiosk-1"}"#,
+        )
+        .unwrap();
+
+        let linux = OverlayTarget {
+            platform: "linux".to_string(),
+            hostname: Some("kiosk-1".to_string()),
+        };
+        let file_handler = file_handler.with_conditional_overlays(linux);
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "kiosk-1");
+        assert_eq!(config.env_config_variable, "linux");
+
+        let saved = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let saved: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(saved["value"], common::FILE_CONFIG_VALUE_SET);
+
+        let windows = OverlayTarget {
+            platform: "windows".to_string(),
+            hostname: None,
+        };
+        let file_handler = file_handler.with_conditional_overlays(windows);
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+
+        fs::write(directory.join("config.windows.json"), "{ invalid").unwrap();
+        let result = file_handler.load_config();
+        assert!(matches!(result, Err(FileConfigParseError::Codec(_))));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_unknown_variables() {
+        env::set_var("LUM_UNKNOWN_VALUE", "value");
+        env::set_var("LUM_UNKNOWN_VALEU", "typo");
+        env::set_var("LUM_UNKNOWN_FILE_CONFIG_VALUE", "nested");
+        env::set_var("LUM_UNKNOWNX_VALUE", "other prefix");
+
+        let env_handler: EnvHandler<common::NestedConfig> = EnvHandler::new("lum_unknown");
+        assert_eq!(
+            env_handler.unknown_variables().unwrap(),
+            vec!["LUM_UNKNOWN_VALEU"]
+        );
+
+        env::set_var("LUM_UNKNOWN_HOSTS_HOSTS_0", "a");
+        env::set_var("LUM_UNKNOWN_HOSTS_PORTZ", "1");
+        let env_handler: EnvHandler<common::HostsConfig> =
+            EnvHandler::new("lum_unknown_hosts").with_array_indices(true);
+        assert_eq!(
+            env_handler.unknown_variables().unwrap(),
+            vec!["LUM_UNKNOWN_HOSTS_PORTZ"]
+        );
+    }
+
+    #[test]
+    fn save_config_if_absent() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let config = common::FileConfig {
+            value: "initial".to_string(),
+            ..common::FileConfig::default()
+        };
+        assert!(file_handler.save_config_if_absent(&config).unwrap());
+        assert_eq!(file_handler.load_config().unwrap().value, "initial");
+
+        fs::write(&file_handler.config_file_path, r#"{"value": "user"}"#).unwrap();
+        assert!(!file_handler.save_config_if_absent(&config).unwrap());
+        assert_eq!(
+            fs::read_to_string(&file_handler.config_file_path).unwrap(),
+            r#"{"value": "user"}"#
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn shared_config() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let config = file_handler.load_config_arc().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+
+        let shared = Arc::new(SharedConfig::new(Arc::into_inner(config).unwrap()));
+        let reader = {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || shared.load().value.clone())
+        };
+        assert_eq!(reader.join().unwrap(), common::FILE_CONFIG_VALUE_SET);
+
+        fs::write(&file_handler.config_file_path, r#"{"value": "reloaded"}"#).unwrap();
+        let before = shared.load();
+        let reloaded = shared.reload(|| file_handler.load_config()).unwrap();
+        assert_eq!(reloaded.value, "reloaded");
+        assert_eq!(shared.load().value, "reloaded");
+        assert_eq!(before.value, common::FILE_CONFIG_VALUE_SET);
+
+        fs::write(&file_handler.config_file_path, "{ invalid").unwrap();
+        assert!(shared.reload(|| file_handler.load_config()).is_err());
+        assert_eq!(shared.load().value, "reloaded");
+
+        let previous = shared.store(common::FileConfig::default());
+        assert_eq!(previous.value, "reloaded");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn merge_same() {
+        let system = common::NestedConfig {
+            value: "system".to_string(),
+            file_config: Some(common::FileConfig {
+                value: "system".to_string(),
+                env_config_variable: "system".to_string(),
+            }),
+        };
+        let user = common::NestedConfig {
+            file_config: Some(common::FileConfig {
+                value: "user".to_string(),
+                ..common::FileConfig::default()
+            }),
+            ..common::NestedConfig::default()
+        };
+
+        let merged = merger::merge_same(system, user).unwrap();
+        assert_eq!(merged.value, "system");
+        let file_config = merged.file_config.unwrap();
+        assert_eq!(file_config.value, "user");
+        // `file_config` defaults to `None`, so the whole nested object of the patch differs from the default.
+        assert_eq!(
+            file_config.env_config_variable,
+            common::ENV_CONFIG_VALUE_NOT_SET
+        );
+
+        let system = common::HostsConfig {
+            hosts: vec!["system".to_string()],
+            ..common::HostsConfig::default()
+        };
+        let merged = merger::merge_same(system, common::HostsConfig::default()).unwrap();
+        assert_eq!(merged.hosts, vec!["system"]);
+    }
+
+    #[test]
+    fn from_path() {
+        let temp_dir = common::get_temp_dir();
+        let config_file_path = temp_dir.join("nested").join("settings.json");
+
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::from_path(&config_file_path);
+        assert_eq!(file_handler.config_file_path, config_file_path);
+        assert_eq!(file_handler.config_directory_path, temp_dir.join("nested"));
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert!(config_file_path.is_file());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_flattened() {
+        env::set_var("LUM_FLATTENED_LABEL", "primary");
+        env::set_var("LUM_FLATTENED_NAME", "server");
+        env::set_var("LUM_FLATTENED_PORT", "8080");
+        env::set_var("LUM_FLATTENED_VERBOSE", "true");
+        env::set_var("LUM_FLATTENED_LISTENER_TCP_PORT", "9090");
+
+        let env_handler: EnvHandler<common::FlattenedConfig> = EnvHandler::new("lum_flattened");
+        assert!(env_handler.load_config().is_err());
+
+        let config = env_handler.load_config_flattened().unwrap();
+        assert_eq!(config.label, "primary");
+        assert_eq!(config.server.name, "server");
+        assert_eq!(config.server.port, 8080);
+        assert!(config.server.verbose);
+        assert_eq!(config.listener.tcp_port, Some(9090));
+        assert_eq!(config.listener.unix_socket, None);
+
+        env::set_var("LUM_FLATTENED_PORT", "eighty");
+        let error = env_handler.load_config_flattened().unwrap_err();
+        assert!(matches!(
+            error,
+            EnvironmentConfigParseError::InvalidVariable { ref name, .. } if name == "LUM_FLATTENED_PORT"
+        ));
+    }
+
+    #[test]
+    fn env_document_variables() {
+        let env_handler: EnvHandler<common::FlattenedConfig> = EnvHandler::new("lum_document");
+        let variables = env_handler.document_variables().unwrap();
+
+        let names: Vec<&str> = variables
+            .iter()
+            .map(|variable| variable.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "LUM_DOCUMENT_LABEL",
+                "LUM_DOCUMENT_LISTENER_TCP_PORT",
+                "LUM_DOCUMENT_LISTENER_UNIX_SOCKET",
+                "LUM_DOCUMENT_NAME",
+                "LUM_DOCUMENT_PORT",
+                "LUM_DOCUMENT_VERBOSE",
+            ]
+        );
+
+        assert_eq!(
+            variables[1],
+            EnvVarDoc {
+                name: "LUM_DOCUMENT_LISTENER_TCP_PORT".to_string(),
+                type_name: "optional",
+                default: None,
+            }
+        );
+        assert_eq!(variables[4].type_name, "integer");
+        assert_eq!(variables[4].default.as_deref(), Some("0"));
+        assert_eq!(variables[5].type_name, "boolean");
+        assert_eq!(variables[5].default.as_deref(), Some("false"));
+
+        let env_handler: EnvHandler<common::MapConfig> = EnvHandler::new("lum_document");
+        let variables = env_handler.document_variables().unwrap();
+        assert!(variables
+            .iter()
+            .any(|variable| variable.type_name == "map" && variable.name.ends_with("_<KEY>")));
+    }
+
+    #[test]
+    fn merge_cloned() {
+        let base = common::PortConfig { port: Some(80) };
+        let patch = common::PortConfig { port: Some(8080) };
+
+        let merged = merger::merge_cloned(&base, &patch);
+        assert_eq!(merged, patch);
+        assert_eq!(base.port, Some(80));
+
+        let merged = merger::merge_cloned(&base, &common::PortConfig::default());
+        assert_eq!(merged, base);
+    }
+
+    #[test]
+    fn with_aliases() {
+        common::captured_messages();
+
+        let temp_dir = common::get_temp_dir();
+        let config_file_path = temp_dir.join("aliases.json");
+        fs::create_dir_all(&temp_dir).unwrap();
+        fs::write(&config_file_path, r#"{"label": "old", "name": "server"}"#).unwrap();
+
+        let file_handler: FileHandler<common::ServerConfig> =
+            FileHandler::from_path(&config_file_path)
+                .with_aliases(&[("label", "name"), ("tcp_port", "port")]);
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.name, "server");
+
+        fs::write(&config_file_path, r#"{"label": "old", "tcp_port": 8080}"#).unwrap();
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.name, "old");
+        assert_eq!(config.port, 8080);
+
+        let content = fs::read_to_string(&config_file_path).unwrap();
+        assert!(!content.contains("label"));
+        assert!(content.contains("\"name\": \"old\""));
+
+        let messages = common::captured_messages();
+        let path = config_file_path.display().to_string();
+        assert!(messages.contains(&format!(
+            "Config key label in {} is deprecated and shadowed by name",
+            path
+        )));
+        assert!(messages.contains(&format!(
+            "Config key tcp_port in {} is deprecated, use port instead",
+            path
+        )));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_ndjson() {
+        let temp_dir = common::get_temp_dir();
+        fs::create_dir_all(&temp_dir).unwrap();
+        let path = temp_dir.join("ports.jsonl");
+        fs::write(&path, "{\"port\": 80}\n\n{\"port\": \"eighty\"}\n{}\n").unwrap();
+
+        let records: Vec<_> = file_handler::load_ndjson::<common::PortConfig, _>(&path).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().unwrap().port, Some(80));
+        let error = records[1].as_ref().unwrap_err();
+        assert!(matches!(
+            error,
+            FileConfigParseError::InvalidRecord { line: 3, .. }
+        ));
+        assert_eq!(error.location().map(|(line, _)| line), Some(3));
+        assert_eq!(records[2].as_ref().unwrap().port, None);
+
+        let result: Result<Vec<common::PortConfig>, _> = file_handler::load_ndjson(&path).collect();
+        assert!(result.is_err());
+
+        let mut missing =
+            file_handler::load_ndjson::<common::PortConfig, _>(temp_dir.join("missing.jsonl"));
+        assert!(matches!(
+            missing.next(),
+            Some(Err(FileConfigParseError::IO(_)))
+        ));
+        assert!(missing.next().is_none());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_flexible_bools() {
+        env::set_var("LUM_FLEXIBLE_BOOLS_NAME", "1");
+        env::set_var("LUM_FLEXIBLE_BOOLS_PORT", "1");
+
+        let env_handler: EnvHandler<common::ServerConfig> =
+            EnvHandler::new("lum_flexible_bools").with_flexible_bools(true);
+        for (value, expected) in [
+            ("1", true),
+            ("yes", true),
+            ("ON", true),
+            ("0", false),
+            ("off", false),
+        ] {
+            env::set_var("LUM_FLEXIBLE_BOOLS_VERBOSE", value);
+            let config = env_handler.load_config().unwrap();
+            assert_eq!(config.verbose, expected, "{}", value);
+            assert_eq!(config.name, "1");
+            assert_eq!(config.port, 1);
+        }
+
+        env::set_var("LUM_FLEXIBLE_BOOLS_VERBOSE", "maybe");
+        let error = env_handler.load_config().unwrap_err();
+        assert!(matches!(
+            error,
+            EnvironmentConfigParseError::InvalidVariable { ref name, ref value, .. }
+                if name == "LUM_FLEXIBLE_BOOLS_VERBOSE" && value == "maybe"
+        ));
+
+        env::set_var("LUM_FLEXIBLE_BOOLS_VERBOSE", "yes");
+        env::set_var("LUM_FLEXIBLE_BOOLS_PORT", "on");
+        let error = env_handler.load_config().unwrap_err();
+        assert!(matches!(
+            error,
+            EnvironmentConfigParseError::InvalidVariable { ref name, ref value, .. }
+                if name == "LUM_FLEXIBLE_BOOLS_PORT" && value == "on"
+        ));
+
+        env::set_var("LUM_FLEXIBLE_BOOLS_PORT", "1");
+        let env_handler: EnvHandler<common::ServerConfig> = EnvHandler::new("lum_flexible_bools");
+        assert!(env_handler.load_config().is_err());
+    }
+
+    #[test]
+    fn with_includes() {
+        let temp_dir = common::get_temp_dir();
+        fs::create_dir_all(temp_dir.join("shared")).unwrap();
+        let config_file_path = temp_dir.join("config.json");
+        let config_content =
+            r#"{"include": ["shared/base.json", "overrides.json"], "name": "main"}"#;
+        fs::write(&config_file_path, config_content).unwrap();
+        fs::write(
+            temp_dir.join("shared").join("base.json"),
+            r#"{"include": ["defaults.json"], "port": 80}"#,
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.join("shared").join("defaults.json"),
+            r#"{"name": "defaults", "port": 1, "verbose": true}"#,
+        )
+        .unwrap();
+        fs::write(temp_dir.join("overrides.json"), r#"{"port": 8080}"#).unwrap();
+
+        let file_handler: FileHandler<common::ServerConfig> =
+            FileHandler::from_path(&config_file_path).with_includes("include");
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.name, "main");
+        assert_eq!(config.port, 8080);
+        assert!(config.verbose);
+        assert_eq!(
+            fs::read_to_string(&config_file_path).unwrap(),
+            config_content
+        );
+
+        fs::write(
+            temp_dir.join("overrides.json"),
+            r#"{"include": ["config.json"]}"#,
+        )
+        .unwrap();
+        let error = file_handler.load_config().unwrap_err();
+        assert!(matches!(
+            error,
+            FileConfigParseError::IncludeCycle(ref path) if path.ends_with("config.json")
+        ));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn get_path() {
+        let config = common::NestedConfig {
+            file_config: Some(common::FileConfig::default()),
+            ..common::NestedConfig::default()
+        };
+
+        let value: String = file_handler::get_path(&config, "file_config.value").unwrap();
+        assert_eq!(value, common::FILE_CONFIG_VALUE_SET);
+        let file_config: common::FileConfig =
+            file_handler::get_path(&config, "file_config").unwrap();
+        assert_eq!(file_config.value, common::FILE_CONFIG_VALUE_SET);
+
+        let error =
+            file_handler::get_path::<String, _>(&config, "file_config.missing").unwrap_err();
+        assert!(
+            matches!(error, FileConfigParseError::NotFound(ref path) if path == "file_config.missing")
+        );
+        let error = file_handler::get_path::<u16, _>(&config, "value").unwrap_err();
+        assert!(matches!(error, FileConfigParseError::Serde(_)));
+    }
+
+    #[test]
+    fn resolve_config_dir() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+
+        let path = file_handler::resolve_config_dir(common::APP_NAME, Some(temp_str)).unwrap();
+        assert_eq!(path, temp_dir.join(common::APP_NAME));
+        assert!(!temp_dir.exists());
+
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        assert_eq!(path, file_handler.config_directory_path);
+    }
+
+    #[test]
+    fn load_sparse() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::ServerConfig> =
+            FileHandler::new("lum_sparse", Some(temp_str), None).unwrap();
+        file_handler
+            .save_config(&common::ServerConfig {
+                name: "from_file".to_string(),
+                port: 80,
+                verbose: true,
+            })
+            .unwrap();
+        env::set_var("LUM_SPARSE_PORT", "8080");
+
+        let config: common::ServerConfig =
+            lum_config::load_sparse("lum_sparse", Some(temp_str), None).unwrap();
+        assert_eq!(config.name, "from_file");
+        assert_eq!(config.port, 8080);
+        assert!(config.verbose);
+
+        let env_handler: EnvHandler<common::ServerConfig> = EnvHandler::new("lum_sparse");
+        assert_eq!(
+            env_handler.load_sparse().unwrap(),
+            serde_json::json!({ "port": 8080 })
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_sparse_without_default() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let config_directory = temp_dir.join("lum_sparse_required");
+        fs::create_dir_all(&config_directory).unwrap();
+        fs::write(
+            config_directory.join("config.json"),
+            r#"{ "name": "from_file", "port": 80 }"#,
+        )
+        .unwrap();
+        env::set_var("LUM_SPARSE_REQUIRED_PORT", "8080");
+
+        let config: common::RequiredFieldConfig =
+            lum_config::load_sparse("lum_sparse_required", Some(temp_str), None).unwrap();
+        assert_eq!(config.name, "from_file");
+        assert_eq!(config.port, 8080);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn try_merge_keep_base() {
+        let base = common::ServerConfig {
+            name: "server".to_string(),
+            port: 80,
+            verbose: false,
+        };
+
+        let merged =
+            merger::try_merge_keep_base(base.clone(), common::PortConfig { port: Some(8080) })
+                .unwrap();
+        assert_eq!(merged.port, 8080);
+
+        let (config, error) =
+            merger::try_merge_keep_base(base, common::PortConfig { port: Some(0) }).unwrap_err();
+        assert_eq!(config.name, "server");
+        assert_eq!(config.port, 80);
+        assert_eq!(error, "port must not be 0");
+    }
+
+    #[test]
+    fn env_case_insensitive() {
+        env::set_var("Lum_Case_Name", "mixed");
+        env::set_var("lum_case_port", "8080");
+        env::set_var("LUM_CASE_VERBOSE", "true");
+        env::set_var("lum_case_verbose", "false");
+
+        let env_handler: EnvHandler<common::ServerConfig> =
+            EnvHandler::new("lum_case").with_case_insensitive(true);
+        let config = env_handler.load_config().unwrap();
+        assert_eq!(config.name, "mixed");
+        assert_eq!(config.port, 8080);
+        assert!(config.verbose);
+
+        let env_handler: EnvHandler<common::ServerConfig> = EnvHandler::new("lum_case")
+            .with_prefix("Lum_Case")
+            .with_case_insensitive(true);
+        assert_eq!(env_handler.resolved_prefix(), "LUM_CASE");
+        assert_eq!(env_handler.load_config().unwrap().port, 8080);
+
+        let env_handler: EnvHandler<common::ServerConfig> = EnvHandler::new("lum_case");
+        let config = env_handler.load_config().unwrap();
+        assert_eq!(config.name, "");
+        assert_eq!(config.port, 0);
+        assert!(config.verbose);
+    }
+
+    #[test]
+    fn empty_file_policy() {
+        let temp_dir = common::get_temp_dir();
+        fs::create_dir_all(&temp_dir).unwrap();
+        let config_file_path = temp_dir.join("config.json");
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::from_path(&config_file_path);
+
+        for content in ["", " \n\t\n"] {
+            fs::write(&config_file_path, content).unwrap();
+            let config = file_handler.load_config().unwrap();
+            assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+            assert!(fs::read_to_string(&config_file_path)
+                .unwrap()
+                .contains(common::FILE_CONFIG_VALUE_SET));
+        }
+
+        let file_handler = file_handler.with_empty_file_policy(EmptyFilePolicy::Error);
+        for content in ["", " \n\t\n"] {
+            fs::write(&config_file_path, content).unwrap();
+            let error = file_handler.load_config().unwrap_err();
+            assert!(
+                matches!(error, FileConfigParseError::EmptyFile(ref path) if *path == config_file_path)
+            );
+            assert_eq!(fs::read_to_string(&config_file_path).unwrap(), content);
+        }
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn convert() {
+        let nested_config = common::NestedConfig {
+            value: "nested".to_string(),
+            file_config: Some(common::FileConfig::default()),
+        };
+
+        let file_config: common::FileConfig = merger::convert(nested_config).unwrap();
+        assert_eq!(file_config.value, "nested");
+        assert_eq!(
+            file_config.env_config_variable,
+            common::ENV_CONFIG_VALUE_NOT_SET
+        );
+
+        let result: Result<common::PortConfig, _> = merger::convert(common::FileConfig::default());
+        assert_eq!(result.unwrap(), common::PortConfig::default());
+        let result: Result<u16, _> = merger::convert(common::FileConfig::default());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn load_with_hooks() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        env::set_var("LUM_HOOKS_VALUE", "From Env");
+
+        let hooks = LoadHooks::default()
+            .with_env_hook(|mut env_config: common::EnvConfig| {
+                env_config.value = env_config.value.map(|value| value.to_lowercase());
+                env_config
+            })
+            .with_file_hook(|mut file_config: common::FileConfig| {
+                file_config.value = file_config.value.to_uppercase();
+                file_config
+            });
+        let config: common::FileConfig =
+            lum_config::load_with_hooks("lum_hooks", Some(temp_str), None, hooks).unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET.to_uppercase());
+        assert_eq!(config.env_config_variable, "from env");
+
+        let config: common::FileConfig = lum_config::load_with_hooks::<_, _, common::EnvConfig>(
+            "lum_hooks",
+            Some(temp_str),
+            None,
+            LoadHooks::default(),
+        )
+        .unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(config.env_config_variable, "From Env");
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_handler_with_file_system_saves_atomically() {
+        let file_system = common::RecordingFs::default()
+            .with_file("/virtual/lum/config.json", "{\"value\": \"Custom\"}");
+        let file_handler: FileHandler<common::FileConfig, JsonCodec, common::RecordingFs> =
+            FileHandler::from_path("/virtual/lum/config.json")
+                .with_file_system(file_system)
+                .with_durable(true);
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "Custom");
+
+        assert_eq!(
+            file_handler.file_system().operations(),
+            vec![
+                "create_dir_all /virtual/lum",
+                "read /virtual/lum/config.json",
+                "create_dir_all /virtual/lum",
+                "write /virtual/lum/.config.json.tmp",
+                "sync /virtual/lum/.config.json.tmp",
+                "rename /virtual/lum/.config.json.tmp",
+                "sync /virtual/lum",
+            ]
+        );
+        let saved = file_handler
+            .file_system()
+            .file(Path::new("/virtual/lum/config.json"))
+            .unwrap();
+        assert!(saved.contains(common::ENV_CONFIG_VALUE_NOT_SET));
+        assert!(file_handler
+            .file_system()
+            .file(Path::new("/virtual/lum/.config.json.tmp"))
+            .is_none());
+    }
+
+    #[test]
+    fn file_handler_with_file_system_creates_missing_file() {
+        let file_handler: FileHandler<common::FileConfig, JsonCodec, common::RecordingFs> =
+            FileHandler::from_path("/virtual/lum/config.json")
+                .with_file_system(common::RecordingFs::default());
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(
+            file_handler.file_system().operations(),
+            vec![
+                "create_dir_all /virtual/lum",
+                "write /virtual/lum/config.json",
+                "read /virtual/lum/config.json",
+                "create_dir_all /virtual/lum",
+                "write /virtual/lum/config.json",
+            ]
+        );
+        assert!(file_handler.exists().unwrap());
+
+        file_handler.delete().unwrap();
+        assert!(!file_handler.exists().unwrap());
+    }
+
+    #[test]
+    fn file_handler_with_file_system_and_json_style() {
+        let file_handler: FileHandler<common::FileConfig, JsonCodec, common::RecordingFs> =
+            FileHandler::from_path("/virtual/lum/config.json")
+                .with_file_system(common::RecordingFs::default())
+                .with_json_style(JsonStyle::compact());
+
+        file_handler
+            .save_config(&common::FileConfig::default())
+            .unwrap();
+        let saved = file_handler
+            .file_system()
+            .file(Path::new("/virtual/lum/config.json"))
+            .unwrap();
+        assert!(!saved.contains('\n'));
+    }
+
+    #[test]
+    fn deep_merge_with_deletion_resets_scalar() {
+        let config = common::FileConfig {
+            value: "Custom".to_string(),
+            env_config_variable: "Custom variable".to_string(),
+        };
+
+        let merged =
+            merger::deep_merge_with_deletion(config, serde_json::json!({ "value": null })).unwrap();
+
+        assert_eq!(merged.value, common::FILE_CONFIG_VALUE_SET);
+        assert_eq!(merged.env_config_variable, "Custom variable");
+    }
+
+    #[test]
+    fn deep_merge_with_deletion_removes_map_entry() {
+        let config = common::MapConfig {
+            zebra: "stripes".to_string(),
+            entries: HashMap::from([
+                ("kept".to_string(), HashMap::from([("a".to_string(), 1)])),
+                (
+                    "nested".to_string(),
+                    HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]),
+                ),
+                ("removed".to_string(), HashMap::from([("a".to_string(), 1)])),
+            ]),
+        };
+
+        let patch = serde_json::json!({
+            "entries": {
+                "nested": { "a": null, "c": 3 },
+                "removed": null,
+                "missing": null,
+            },
+        });
+        let merged = merger::deep_merge_with_deletion(config, patch).unwrap();
+
+        assert_eq!(merged.zebra, "stripes");
+        assert_eq!(
+            merged.entries,
+            HashMap::from([
+                ("kept".to_string(), HashMap::from([("a".to_string(), 1)])),
+                (
+                    "nested".to_string(),
+                    HashMap::from([("b".to_string(), 2), ("c".to_string(), 3)]),
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn flatten_nested_objects() {
+        let config = common::NestedConfig {
+            file_config: Some(common::FileConfig::default()),
+            ..common::NestedConfig::default()
+        };
+
+        let flat = file_handler::flatten(&config, "_").unwrap();
+
+        assert_eq!(
+            flat,
+            BTreeMap::from([
+                (
+                    "file_config_value".to_string(),
+                    common::FILE_CONFIG_VALUE_SET.to_string()
+                ),
+                (
+                    "file_config_env_config_variable".to_string(),
+                    common::ENV_CONFIG_VALUE_NOT_SET.to_string()
+                ),
+                (
+                    "value".to_string(),
+                    common::NESTED_CONFIG_VALUE_SET.to_string()
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn flatten_arrays_and_non_string_leaves() {
+        let config = serde_json::json!({
+            "hosts": ["a", "b"],
+            "ports": [],
+            "server": { "port": 8080, "verbose": true, "name": null, "tags": {} },
+        });
+
+        let flat = file_handler::flatten(&config, ".").unwrap();
+
+        assert_eq!(
+            flat,
+            BTreeMap::from([
+                ("hosts.0".to_string(), "a".to_string()),
+                ("hosts.1".to_string(), "b".to_string()),
+                ("ports".to_string(), "[]".to_string()),
+                ("server.name".to_string(), "null".to_string()),
+                ("server.port".to_string(), "8080".to_string()),
+                ("server.tags".to_string(), "{}".to_string()),
+                ("server.verbose".to_string(), "true".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn env_tagged_enums() {
+        env::set_var("LUM_TAGGED_INTERNAL_TYPE", "redis");
+        env::set_var("LUM_TAGGED_INTERNAL_URL", "redis://localhost");
+        env::set_var("LUM_TAGGED_INTERNAL_DATABASE", "2");
+        env::set_var("LUM_TAGGED_ADJACENT_TYPE", "redis");
+        env::set_var("LUM_TAGGED_ADJACENT_CONFIG_URL", "redis://adjacent");
+        env::set_var("LUM_TAGGED_FALLBACK_TYPE", "redis");
+        env::set_var(
+            "LUM_TAGGED_FALLBACK_CONFIG",
+            r#"{"url": "redis://fallback"}"#,
+        );
+
+        let env_handler: EnvHandler<common::BackendConfig> = EnvHandler::new("lum_tagged");
+        assert!(env_handler.load_config().is_err());
+        assert!(env_handler.load_config_flattened().is_err());
+        assert_eq!(env_handler.unknown_variables().unwrap().len(), 4);
+
+        let env_handler = env_handler.with_tagged_enums(true);
+        let config = env_handler.load_config_flattened().unwrap();
+        assert_eq!(config.name, "cache");
+        assert_eq!(
+            config.internal,
+            common::InternallyTaggedBackend::Redis {
+                url: "redis://localhost".to_string(),
+                database: 2,
+            }
+        );
+        assert_eq!(
+            config.adjacent,
+            common::AdjacentlyTaggedBackend::Redis {
+                url: "redis://adjacent".to_string(),
+            }
+        );
+        assert_eq!(
+            config.fallback,
+            common::AdjacentlyTaggedBackend::Redis {
+                url: "redis://fallback".to_string(),
+            }
+        );
+        assert!(env_handler.unknown_variables().unwrap().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_handler_diagnose() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let diagnostics = file_handler.diagnose();
+        assert_eq!(diagnostics.directory, PathStatus::Missing);
+        assert_eq!(diagnostics.file, PathStatus::Missing);
+        assert!(diagnostics.is_ok());
+        assert_eq!(diagnostics.to_string(), "No problems found");
+
+        file_handler.load_config().unwrap();
+        let diagnostics = file_handler.diagnose();
+        assert_eq!(diagnostics.directory, PathStatus::Writable);
+        assert_eq!(diagnostics.file, PathStatus::Writable);
+
+        let config_file_path = &file_handler.config_file_path;
+        let config_directory_path = &file_handler.config_directory_path;
+        fs::set_permissions(config_file_path, fs::Permissions::from_mode(0o444)).unwrap();
+        fs::set_permissions(config_directory_path, fs::Permissions::from_mode(0o555)).unwrap();
+        let diagnostics = file_handler.diagnose();
+        assert_eq!(diagnostics.directory, PathStatus::ReadOnly);
+        assert_eq!(diagnostics.file, PathStatus::ReadOnly);
+        assert!(!diagnostics.is_ok());
+        assert_eq!(
+            diagnostics.messages(),
+            vec![
+                format!(
+                    "Config directory {} exists but is not writable, make it writable for the current user",
+                    config_directory_path.display()
+                ),
+                format!(
+                    "Config file {} exists but is not writable, make it writable for the current user",
+                    config_file_path.display()
+                ),
+            ]
+        );
+
+        fs::set_permissions(config_directory_path, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_file(config_file_path).unwrap();
+        fs::create_dir(config_file_path).unwrap();
+        let diagnostics = file_handler.diagnose();
+        assert_eq!(diagnostics.file, PathStatus::WrongType);
+        assert!(diagnostics.to_string().contains("is not a file"));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn merge_optionals() {
+        let config = common::ServerConfig {
+            name: "server".to_string(),
+            port: 80,
+            verbose: true,
+        };
+        let patch = common::PartialServerConfig {
+            port: Some(8080),
+            verbose: Some(false),
+            ..common::PartialServerConfig::default()
+        };
+
+        let merged = merger::merge_optionals(config, patch).unwrap();
+        assert_eq!(merged.name, "server");
+        assert_eq!(merged.port, 8080);
+        assert!(!merged.verbose);
+
+        let unchanged =
+            merger::merge_optionals(merged, common::PartialServerConfig::default()).unwrap();
+        assert_eq!(unchanged.name, "server");
+        assert_eq!(unchanged.port, 8080);
+    }
+
+    #[test]
+    fn merge_optionals_nested() {
+        let config = common::NestedConfig {
+            value: "outer".to_string(),
+            file_config: Some(common::FileConfig::default()),
+        };
+        let patch = common::PartialNestedConfig {
+            value: None,
+            file_config: Some(common::PartialFileConfig {
+                value: Some("inner".to_string()),
+                env_config_variable: None,
+            }),
+        };
+
+        let merged = merger::merge_optionals(config, patch).unwrap();
+        assert_eq!(merged.value, "outer");
+        let file_config = merged.file_config.unwrap();
+        assert_eq!(file_config.value, "inner");
+        assert_eq!(
+            file_config.env_config_variable,
+            common::ENV_CONFIG_VALUE_NOT_SET
+        );
+    }
+
+    #[test]
+    fn file_handler_stdio() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let stdout = common::SharedBuffer::default();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), Some("-"))
+                .unwrap()
+                .with_stdio(
+                    io::Cursor::new(r#"{"value": "From stdin"}"#),
+                    stdout.clone(),
+                );
+        assert!(file_handler.is_stdio());
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, "From stdin");
+        assert_eq!(config.env_config_variable, common::ENV_CONFIG_VALUE_NOT_SET);
+        assert!(stdout.contents().is_empty());
+        assert!(!temp_dir.exists());
+
+        file_handler.save_config(&config).unwrap();
+        let written: Value = serde_json::from_str(&stdout.contents()).unwrap();
+        assert_eq!(written["value"], "From stdin");
+        assert!(!temp_dir.exists());
+
+        let config = file_handler.load_config().unwrap();
+        assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+    }
+
+    #[test]
+    fn merge_vec_by_key() {
+        let server = |name: &str, port: u16| common::ServerConfig {
+            name: name.to_string(),
+            port,
+            verbose: false,
+        };
+        let base = vec![server("a", 80), server("b", 81), server("c", 82)];
+        let patch = vec![
+            server("d", 90),
+            common::ServerConfig {
+                verbose: true,
+                ..server("b", 8081)
+            },
+            server("e", 91),
+        ];
+
+        let merged = merger::merge_vec_by_key(
+            base,
+            patch,
+            |server| server.name.clone(),
+            |base, patch| common::ServerConfig {
+                port: patch.port,
+                ..base
+            },
+        );
+
+        let summary: Vec<(String, u16, bool)> = merged
+            .into_iter()
+            .map(|server| (server.name, server.port, server.verbose))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("a".to_string(), 80, false),
+                ("b".to_string(), 8081, false),
+                ("c".to_string(), 82, false),
+                ("d".to_string(), 90, false),
+                ("e".to_string(), 91, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn file_handler_without_create_dirs() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_create_dirs(false);
+
+        let error = file_handler.load_config().unwrap_err();
+        assert!(matches!(
+            error,
+            FileConfigParseError::IO(ref error) if error.kind() == io::ErrorKind::NotFound
+        ));
+        assert!(error.to_string().contains("creating it is disabled"));
+        let error = file_handler
+            .save_config(&common::FileConfig::default())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigSaveError::IO(ref error) if error.kind() == io::ErrorKind::NotFound
+        ));
+        assert!(!temp_dir.exists());
+
+        file_handler.create_config_directory().unwrap();
+        let file_config = file_handler.load_config().unwrap();
+        assert_eq!(file_config.value, common::FILE_CONFIG_VALUE_SET);
+        assert!(file_handler.config_file_path.exists());
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_sparse_overrides_nested_leaf() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let database = common::DatabaseConfig {
+            host: "db.internal".to_string(),
+            user: "lum".to_string(),
+            pool: common::PoolConfig { min: 5, max: 10 },
+        };
+        let file_config = common::DatabaseAppConfig {
+            name: "from_file".to_string(),
+            database: database.clone(),
+            replica: Some(database),
+        };
+        let file_handler: FileHandler<common::DatabaseAppConfig> =
+            FileHandler::new("lum_surgical", Some(temp_str), None).unwrap();
+        file_handler.save_config(&file_config).unwrap();
+        env::set_var("LUM_SURGICAL_DATABASE_POOL_MAX", "50");
+        env::set_var("LUM_SURGICAL_REPLICA_POOL_MIN", "1");
+
+        let config: common::DatabaseAppConfig =
+            lum_config::load_sparse("lum_surgical", Some(temp_str), None).unwrap();
+
+        let mut expected = file_config;
+        expected.database.pool.max = 50;
+        expected.replica.as_mut().unwrap().pool.min = 1;
+        assert_eq!(config, expected);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn file_handler_normalize() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::MapConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+        file_handler.create_config_directory().unwrap();
+        fs::write(
+            &file_handler.config_file_path,
+            r#"{"entries":{"b":{"y":2,"x":1},"a":{"z":3}}}"#,
+        )
+        .unwrap();
+
+        let config = file_handler.normalize().unwrap();
+        assert_eq!(config.entries["b"]["x"], 1);
+        let normalized = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        let expected = serde_json::to_string_pretty(&serde_json::json!({
+            "entries": { "a": { "z": 3 }, "b": { "x": 1, "y": 2 } },
+            "zebra": "",
+        }))
+        .unwrap();
+        assert_eq!(normalized, expected);
+
+        file_handler.normalize().unwrap();
+        let renormalized = fs::read_to_string(&file_handler.config_file_path).unwrap();
+        assert_eq!(renormalized, normalized);
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_get() {
+        env::set_var("LUM_GET_VALUE", "Single value");
+        env::set_var("LUM_GET_DATABASE_POOL_MAX", "50");
+        env::set_var("LUM_GET_HOSTS", "a,b");
+        env::set_var("LUM_GET_VERBOSE", "yes");
+
+        let env_handler: EnvHandler<common::EnvConfig> = EnvHandler::new("lum_get");
+        assert_eq!(
+            env_handler.get::<String>("value").unwrap().as_deref(),
+            Some("Single value")
+        );
+        assert_eq!(
+            env_handler.get::<u32>("database.pool.max").unwrap(),
+            Some(50)
+        );
+        assert_eq!(
+            env_handler.get::<u32>("DATABASE_POOL_MAX").unwrap(),
+            Some(50)
+        );
+        assert_eq!(
+            env_handler.get::<Vec<String>>("hosts").unwrap(),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(env_handler.get::<u32>("database.pool.min").unwrap(), None);
+
+        let error = env_handler.get::<u16>("value").unwrap_err();
+        assert!(matches!(
+            error,
+            EnvironmentConfigParseError::InvalidVariable { ref name, .. } if name == "LUM_GET_VALUE"
+        ));
+        assert!(env_handler.get::<bool>("verbose").is_err());
+
+        let env_handler = env_handler.with_flexible_bools(true);
+        assert_eq!(env_handler.get::<bool>("verbose").unwrap(), Some(true));
+    }
+
+    #[test]
+    fn env_file_round_trip() {
+        let config = common::ServerConfig {
+            name: "main server # 1\n\"quoted\" \\ 'single'".to_string(),
+            port: 8080,
+            verbose: true,
+        };
+
+        let mut env_file = Vec::new();
+        env_handler::save_to_writer(&config, &mut env_file, "LUM_SNAPSHOT").unwrap();
+        let loaded: common::ServerConfig =
+            env_handler::load_from_reader(env_file.as_slice(), "LUM_SNAPSHOT").unwrap();
+        assert_eq!(loaded.name, config.name);
+        assert_eq!(loaded.port, 8080);
+        assert!(loaded.verbose);
+
+        let hosts = common::HostsConfig {
+            hosts: vec!["a".to_string(), "b".to_string()],
+            ports: vec![80, 443],
+            fallbacks: Vec::new(),
+        };
+        let mut env_file = Vec::new();
+        env_handler::save_to_writer(&hosts, &mut env_file, "LUM_SNAPSHOT").unwrap();
+        assert_eq!(
+            String::from_utf8(env_file.clone()).unwrap(),
+            "LUM_SNAPSHOT_FALLBACKS=\"\"\nLUM_SNAPSHOT_HOSTS=a,b\nLUM_SNAPSHOT_PORTS=80,443\n"
+        );
+        let loaded: common::HostsConfig =
+            env_handler::load_from_reader(env_file.as_slice(), "LUM_SNAPSHOT").unwrap();
+        assert_eq!(loaded, hosts);
+    }
+
+    #[test]
+    fn env_file_parsing() {
+        let env_file = r#"
+# Snapshot of the production deployment
+
+export LUM_PARSED_NAME='literal \n # value'
+LUM_PARSED_PORT = 9090 # the default port
+LUM_PARSED_VERBOSE="true"
+OTHER_VARIABLE=ignored
+"#;
+        let config: common::ServerConfig =
+            env_handler::load_from_reader(env_file.as_bytes(), "LUM_PARSED").unwrap();
+        assert_eq!(config.name, r"literal \n # value");
+        assert_eq!(config.port, 9090);
+        assert!(config.verbose);
+
+        let error = env_handler::load_from_reader::<common::ServerConfig, _>(
+            "LUM_PARSED_PORT=1\nLUM_PARSED_NAME=\"unterminated\n".as_bytes(),
+            "LUM_PARSED",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            EnvironmentConfigParseError::InvalidLine { line: 2, .. }
+        ));
+
+        let error = env_handler::load_from_reader::<common::ServerConfig, _>(
+            "LUM_PARSED_PORT=eighty\n".as_bytes(),
+            "LUM_PARSED",
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            EnvironmentConfigParseError::InvalidVariable { ref name, .. } if name == "LUM_PARSED_PORT"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_handler_with_temp_dir() {
+        common::captured_messages();
+
+        let file_system = common::RecordingFs::default()
+            .with_directory("/spool")
+            .with_directory("/mnt/other")
+            .with_mount("/mnt", 1);
+        let file_handler: FileHandler<common::FileConfig, JsonCodec, common::RecordingFs> =
+            FileHandler::from_path("/virtual/lum/config.json")
+                .with_file_system(file_system)
+                .with_durable(true)
+                .with_temp_dir("/spool");
+
+        file_handler
+            .save_config(&common::FileConfig::default())
+            .unwrap();
+        assert_eq!(
+            file_handler.file_system().operations(),
+            vec![
+                "create_dir_all /virtual/lum",
+                "write /spool/.config.json.tmp",
+                "sync /spool/.config.json.tmp",
+                "rename /spool/.config.json.tmp",
+                "sync /virtual/lum",
+            ]
+        );
+        assert!(file_handler
+            .file_system()
+            .file(Path::new("/virtual/lum/config.json"))
+            .is_some());
+
+        let file_handler = file_handler.with_temp_dir("/mnt/other");
+        file_handler
+            .save_config(&common::FileConfig::default())
+            .unwrap();
+        let operations = file_handler.file_system().operations();
+        assert_eq!(operations[6], "write /virtual/lum/.config.json.tmp");
+        assert!(common::captured_messages()
+            .iter()
+            .any(|message| message.contains("Temp directory /mnt/other is on another filesystem")));
+    }
+
+    #[cfg(feature = "human-values")]
+    #[test]
+    fn config_serde_human_values_round_trip() {
+        let config: common::LimitsConfig =
+            serde_json::from_str(r#"{ "timeout": "2m 30s", "max_size": "10MB" }"#).unwrap();
+        assert_eq!(config.timeout, Duration::from_secs(150));
+        assert_eq!(config.max_size, 10_000_000);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["timeout"], "2m 30s");
+        assert_eq!(json["max_size"], "10MB");
+
+        let error =
+            serde_json::from_str::<common::LimitsConfig>(r#"{ "max_size": "10 XB" }"#).unwrap_err();
+        assert!(error.to_string().contains("invalid byte size \"10 XB\""));
+
+        let error = serde_json::from_str::<common::LimitsConfig>(
+            r#"{ "max_size": "0.11111111111111111111111111111111111PB" }"#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("too many decimal places"));
+
+        let error =
+            serde_json::from_str::<common::LimitsConfig>(r#"{ "timeout": 30 }"#).unwrap_err();
+        assert!(error.to_string().contains("a duration like"));
+    }
+
+    #[cfg(feature = "human-values")]
+    #[test]
+    fn config_serde_human_values_from_env() {
+        env::set_var("LUM_LIMITS_TIMEOUT", "500ms");
+        env::set_var("LUM_LIMITS_MAX_SIZE", "1.5 kib");
+
+        let env_handler: EnvHandler<common::LimitsConfig> = EnvHandler::new("lum_limits");
+        let config = env_handler.load_config().unwrap();
+        assert_eq!(config.timeout, Duration::from_millis(500));
+        assert_eq!(config.max_size, 1536);
+    }
+
+    #[test]
+    fn on_create_hook() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let created = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&created);
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_on_create(move |path| {
+                    let content = fs::read_to_string(path)?;
+                    recorded.lock().unwrap().push(content);
+                    Ok(())
+                });
+
+        file_handler.load_config().unwrap();
+        file_handler.load_config().unwrap();
+
+        let created = created.lock().unwrap();
+        assert_eq!(created.len(), 1);
+        assert!(created[0].contains(common::ENV_CONFIG_VALUE_NOT_SET));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn on_create_hook_error() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_on_create(|_| Err("permissions not supported".into()));
+
+        let error = file_handler.load_config().unwrap_err();
+        assert!(matches!(
+            &error,
+            FileConfigParseError::OnCreate { path, .. } if *path == file_handler.config_file_path
+        ));
+        assert!(error.to_string().ends_with("permissions not supported"));
+
+        assert!(file_handler.config_file_path.is_file());
+        file_handler.load_config().unwrap();
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn is_in_sync() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        let mut config = common::FileConfig::default();
+        assert!(!file_handler.is_in_sync(&config).unwrap());
+        assert!(!file_handler.config_file_path.exists());
+
+        file_handler.create_config_directory().unwrap();
+        // Formatting, key order and defaulted fields are not differences
+        fs::write(
+            &file_handler.config_file_path,
+            format!(
+                "{{\n\n  \"env_config_variable\":    \"{}\"\n}}",
+                common::ENV_CONFIG_VALUE_NOT_SET
+            ),
+        )
+        .unwrap();
+        assert!(file_handler.is_in_sync(&config).unwrap());
+
+        config.value = "changed".to_string();
+        assert!(!file_handler.is_in_sync(&config).unwrap());
+        file_handler.save_config(&config).unwrap();
+        assert!(file_handler.is_in_sync(&config).unwrap());
+
+        fs::write(&file_handler.config_file_path, "{ invalid").unwrap();
+        assert!(matches!(
+            file_handler.is_in_sync(&config),
+            Err(FileConfigParseError::Codec(_))
+        ));
+        assert_eq!(
+            fs::read_to_string(&file_handler.config_file_path).unwrap(),
+            "{ invalid"
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn env_required_variables() {
+        env::set_var("LUM_REQUIRED_NAME", "app");
+        env::set_var("LUM_OLD_REQUIRED_DATABASE_USER", "admin");
+
+        let env_handler: EnvHandler<common::DatabaseAppConfig> = EnvHandler::new("lum_required")
+            .with_additional_prefix("lum_old_required")
+            .require(&[
+                "name",
+                "database.host",
+                "DATABASE_USER",
+                "database_pool_max",
+            ]);
+
+        assert!(matches!(
+            env_handler.load_config(),
+            Err(EnvironmentConfigParseError::MissingRequired(missing))
+                if missing == ["LUM_REQUIRED_DATABASE_HOST", "LUM_REQUIRED_DATABASE_POOL_MAX"]
+        ));
+        assert!(matches!(
+            env_handler.load_sparse(),
+            Err(EnvironmentConfigParseError::MissingRequired(missing)) if missing.len() == 2
+        ));
+
+        env::set_var("LUM_REQUIRED_DATABASE_HOST", "localhost");
+        env::set_var("LUM_REQUIRED_DATABASE_POOL_MAX", "10");
+        let config = env_handler.load_config_flattened().unwrap();
+        assert_eq!(config.database.user, "admin");
+        assert_eq!(config.database.pool.max, 10);
+    }
+
+    #[test]
+    fn load_config_recover_ignores_mismatched_config() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::RequiredFieldConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None).unwrap();
+
+        file_handler.create_config_directory().unwrap();
+        for document in [r#"{"name": "app"}"#, r#"{"name": "app", "port": "high"}"#] {
+            fs::write(&file_handler.config_file_path, document).unwrap();
+            assert!(matches!(
+                file_handler.load_config_recover(),
+                Err(FileConfigParseError::Codec(_))
+            ));
+            assert_eq!(
+                fs::read_to_string(&file_handler.config_file_path).unwrap(),
+                document
+            );
+        }
+        assert_eq!(
+            fs::read_dir(&file_handler.config_directory_path)
+                .unwrap()
+                .count(),
+            1
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn load_config_recover_ignores_broken_include() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_includes("include");
+
+        file_handler.create_config_directory().unwrap();
+        let document = r#"{"include": ["extra.json"], "value": "main"}"#;
+        fs::write(&file_handler.config_file_path, document).unwrap();
+        let include_path = file_handler.config_directory_path.join("extra.json");
+        fs::write(&include_path, "{ not json").unwrap();
+
+        let error = file_handler.load_config_recover().unwrap_err();
+        assert!(matches!(
+            error,
+            FileConfigParseError::Include { ref path, ref source }
+                if *path == include_path && matches!(**source, FileConfigParseError::Codec(_))
+        ));
+        assert_eq!(
+            fs::read_to_string(&file_handler.config_file_path).unwrap(),
+            document
+        );
+        assert_eq!(
+            fs::read_dir(&file_handler.config_directory_path)
+                .unwrap()
+                .count(),
+            2
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn max_size_patch_and_content_hash() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_max_size(16);
+
+        file_handler.create_config_directory().unwrap();
+        let document = r#"{"value": "abcd"}"#;
+        fs::write(&file_handler.config_file_path, document).unwrap();
+
+        let error = file_handler.content_hash().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(matches!(
+            file_handler.patch(&serde_json::json!({ "value": "a" })),
+            Err(ConfigSaveError::IO(error)) if error.kind() == io::ErrorKind::InvalidData
+        ));
+        assert_eq!(
+            fs::read_to_string(&file_handler.config_file_path).unwrap(),
+            document
+        );
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[test]
+    fn max_size_multibyte() {
+        let temp_dir = common::get_temp_dir();
+        let temp_str = temp_dir.to_str().unwrap();
+        let file_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), None)
+                .unwrap()
+                .with_max_size(10);
+
+        file_handler.create_config_directory().unwrap();
+        // The limit ends in the middle of the two bytes of "é"
+        fs::write(&file_handler.config_file_path, r#"{"value":"é"}"#).unwrap();
+        assert!(matches!(
+            file_handler.load_config(),
+            Err(FileConfigParseError::TooLarge { max_size: 10, .. })
+        ));
+
+        let stdio_handler: FileHandler<common::FileConfig> =
+            FileHandler::new(common::APP_NAME, Some(temp_str), Some("-"))
+                .unwrap()
+                .with_max_size(10)
+                .with_stdio(io::Cursor::new(r#"{"value":"é"}"#), io::sink());
+        assert!(matches!(
+            stdio_handler.load_config(),
+            Err(FileConfigParseError::TooLarge { max_size: 10, .. })
+        ));
+
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn load_with_options_allow_missing_file_layer_read_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        env::set_var("LUM_READ_ONLY_FILE_LAYER_PORT", "8080");
+
+        let temp_dir = common::get_temp_dir();
+        let config_directory_path = temp_dir.join("lum_read_only_file_layer");
+        fs::create_dir_all(&config_directory_path).unwrap();
+        fs::set_permissions(&config_directory_path, fs::Permissions::from_mode(0o555)).unwrap();
+        let writable = fs::write(config_directory_path.join("probe"), "").is_ok();
+        let config_directory = temp_dir.to_str().unwrap().to_string();
+
+        // Privileged users (e.g. root in CI containers) can write to read-only directories, so there is nothing to test.
+        if !writable {
+            let result = load_with_options::<_, common::FileConfig, common::PortConfig>(
+                "lum_read_only_file_layer".to_string(),
+                Some(config_directory.clone()),
+                None,
+                LoadOptions::default(),
+            );
+            assert!(matches!(
+                result,
+                Err(ConfigLoadError::ParseFile(FileConfigParseError::IO(_)))
+            ));
+
+            let options = LoadOptions {
+                allow_missing_file_layer: true,
+                ..LoadOptions::default()
+            };
+            let config = load_with_options::<_, common::FileConfig, common::PortConfig>(
+                "lum_read_only_file_layer".to_string(),
+                Some(config_directory),
+                None,
+                options,
+            )
+            .unwrap();
+            assert_eq!(config.value, common::FILE_CONFIG_VALUE_SET);
+            assert_eq!(config.env_config_variable, "8080");
+        }
+
+        fs::set_permissions(&config_directory_path, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(temp_dir).unwrap();
+    }
 }